@@ -1,64 +1,240 @@
-use datum::{Datum, Procedure};
+use datum::Datum;
 use environment::Environment;
 use error::RuntimeError;
+use hash_table::{Equivalence, HashTable};
+use number::{self, Number};
+use promise::{Promise, PromiseState};
+use record::{Record, RecordType};
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
+use symbol::Symbol;
 use vm::{Instruction, DefineType};
 
 pub fn get_builtins() -> Vec<(&'static str, Datum)>
 {
+    // make-hash-table recognizes the equivalence predicates by identity.
+    let eq = Datum::native(native_eqv_p);
+    let eqv = Datum::native(native_eqv_p);
+    let equal = Datum::native(native_equal_p);
+    let string_equal = Datum::native(native_string_equal_p);
+    let equivalences = vec![
+        (eq.clone(), Equivalence::Eq),
+        (eqv.clone(), Equivalence::Eqv),
+        (equal.clone(), Equivalence::Equal),
+        (string_equal.clone(), Equivalence::String)
+    ];
+    let call_cc = Datum::special(special_form_call_cc);
     vec![
         ("begin", Datum::special(special_form_begin)),
+        ("call-with-current-continuation", call_cc.clone()),
+        ("call-with-values", Datum::special(special_form_call_with_values)),
+        ("call/cc", call_cc),
         ("define", Datum::special(special_form_define)),
+        ("define-record-type", Datum::special(special_form_define_record_type)),
         ("define-syntax", Datum::special(special_form_define_syntax)),
+        ("define-values", Datum::special(special_form_define_values)),
+        ("delay", Datum::special(special_form_delay)),
+        ("delay-force", Datum::special(special_form_delay_force)),
         ("eval", Datum::special(special_form_eval)),
+        ("force", Datum::special(special_form_force)),
         ("if", Datum::special(special_form_if)),
         ("lambda", Datum::special(special_form_lambda)),
+        ("let-values", Datum::special(special_form_let_values)),
+        ("let*-values", Datum::special(special_form_let_star_values)),
         ("letrec", Datum::special(special_form_letrec)),
+        ("make-parameter", Datum::special(special_form_make_parameter)),
+        ("parameterize", Datum::special(special_form_parameterize)),
         ("quote", Datum::special(special_form_quote)),
+        ("receive", Datum::special(special_form_receive)),
         ("set!", Datum::special(special_form_set)),
         ("syntax-rules", Datum::special(special_form_syntax_rules)),
 
         ("+", Datum::native(native_add)),
         ("-", Datum::native(native_subtract)),
         ("*", Datum::native(native_multiply)),
+        ("/", Datum::native(native_divide)),
         ("=", Datum::native(native_equals)),
+        ("<", Datum::native(native_less)),
+        (">", Datum::native(native_greater)),
+        ("<=", Datum::native(native_less_equal)),
+        (">=", Datum::native(native_greater_equal)),
+        ("abs", Datum::native(native_abs)),
         ("append", Datum::native(native_append)),
+        ("assq", Datum::native(native_assv)), // same as assv
+        ("assv", Datum::native(native_assv)),
+        ("arithmetic-shift", Datum::native(native_arithmetic_shift)),
+        ("bit-count", Datum::native(native_bit_count)),
+        ("bit-field", Datum::native(native_bit_field)),
+        ("bitwise-and", Datum::native(native_bitwise_and)),
+        ("bitwise-not", Datum::native(native_bitwise_not)),
+        ("bitwise-or", Datum::native(native_bitwise_or)),
+        ("bitwise-xor", Datum::native(native_bitwise_xor)),
+        ("bytevector", Datum::native(native_bytevector)),
+        ("bytevector-append", Datum::native(native_bytevector_append)),
+        ("bytevector-copy", Datum::native(native_bytevector_copy)),
+        ("bytevector-copy!", Datum::native(native_bytevector_copy_to)),
+        ("bytevector-length", Datum::native(native_bytevector_length)),
+        ("bytevector-u8-ref", Datum::native(native_bytevector_u8_ref)),
+        ("bytevector-u8-set!", Datum::native(native_bytevector_u8_set)),
         ("car", Datum::native(native_car)),
         ("cdr", Datum::native(native_cdr)),
+        ("ceiling", Datum::native(native_ceiling)),
+        ("char=?", Datum::native(native_char_equal_p)),
+        ("char<?", Datum::native(native_char_less_p)),
+        ("char>?", Datum::native(native_char_greater_p)),
+        ("char<=?", Datum::native(native_char_less_equal_p)),
+        ("char>=?", Datum::native(native_char_greater_equal_p)),
+        ("char-ci=?", Datum::native(native_char_ci_equal_p)),
+        ("char-ci<?", Datum::native(native_char_ci_less_p)),
+        ("char-ci>?", Datum::native(native_char_ci_greater_p)),
+        ("char-ci<=?", Datum::native(native_char_ci_less_equal_p)),
+        ("char-ci>=?", Datum::native(native_char_ci_greater_equal_p)),
+        ("char-downcase", Datum::native(native_char_downcase)),
+        ("char-foldcase", Datum::native(native_char_foldcase)),
+        ("char->integer", Datum::native(native_char_to_integer)),
+        ("char-upcase", Datum::native(native_char_upcase)),
         ("cons", Datum::native(native_cons)),
-        ("eq?", Datum::native(native_eqv_p)), // same as eqv?
-        ("equal?", Datum::native(native_equal_p)),
-        ("eqv?", Datum::native(native_eqv_p)),
+        ("denominator", Datum::native(native_denominator)),
+        ("digit-value", Datum::native(native_digit_value)),
+        ("eq?", eq), // same as eqv?
+        ("equal?", equal),
+        ("eqv?", eqv),
+        ("eof-object", Datum::native(native_eof_object)),
+        ("exact", Datum::native(native_exact)),
+        ("exact-integer-sqrt",
+            Datum::native_values(native_exact_integer_sqrt)),
+        ("expt", Datum::native(native_expt)),
+        ("floor", Datum::native(native_floor)),
+        ("floor/", Datum::native_values(native_floor_divide)),
+        ("floor-quotient", Datum::native(native_floor_quotient)),
+        ("floor-remainder", Datum::native(native_floor_remainder)),
+        ("gcd", Datum::native(native_gcd)),
+        ("generate-temporary", Datum::native(native_generate_temporary)),
+        ("gensym", Datum::native(native_gensym)),
         ("hash-ref", Datum::native(native_hash_ref)),
         ("hash-set!", Datum::native(native_hash_set)),
+        ("hash-table-contains?", Datum::native(native_hash_table_contains_p)),
+        ("hash-table-copy", Datum::native(native_hash_table_copy)),
+        ("hash-table-count", Datum::native(native_hash_table_count)),
+        ("hash-table-delete!", Datum::native(native_hash_table_delete)),
+        ("hash-table-keys", Datum::native(native_hash_table_keys)),
+        ("hash-table-ref", Datum::native(native_hash_table_ref)),
+        ("hash-table-ref/default",
+            Datum::native(native_hash_table_ref_default)),
+        ("hash-table-set!", Datum::native(native_hash_set)),
+        ("hash-table-values", Datum::native(native_hash_table_values)),
+        ("hash-table->alist", Datum::native(native_hash_table_to_alist)),
+        ("inexact", Datum::native(native_inexact)),
+        ("iota", Datum::native(native_iota)),
+        ("integer-length", Datum::native(native_integer_length)),
+        ("integer->char", Datum::native(native_integer_to_char)),
+        ("last-pair", Datum::native(native_last_pair)),
+        ("lcm", Datum::native(native_lcm)),
         ("length", Datum::native(native_length)),
         ("list", Datum::native(native_list)),
+        ("list-copy", Datum::native(native_list_copy)),
+        ("list-ref", Datum::native(native_list_ref)),
+        ("list-tail", Datum::native(native_list_tail)),
         ("list->string", Datum::native(native_list_to_string)),
-        ("make-hash-table", Datum::native(native_make_hash_table)),
+        ("list->vector", Datum::native(native_list_to_vector)),
+        ("make-bytevector", Datum::native(native_make_bytevector)),
+        ("make-hash-table", Datum::native(move |args|
+            make_hash_table(args, &equivalences))),
+        ("make-promise", Datum::native(native_make_promise)),
+        ("make-string", Datum::native(native_make_string)),
+        ("make-vector", Datum::native(native_make_vector)),
+        ("max", Datum::native(native_max)),
+        ("memq", Datum::native(native_memv)), // same as memv
+        ("memv", Datum::native(native_memv)),
+        ("min", Datum::native(native_min)),
+        ("modulo", Datum::native(native_floor_remainder)),
         ("null?", Datum::native(native_null_p)),
+        ("number->string", Datum::native(native_number_to_string)),
+        ("numerator", Datum::native(native_numerator)),
+        ("quotient", Datum::native(native_truncate_quotient)),
+        ("remainder", Datum::native(native_truncate_remainder)),
         ("reverse", Datum::native(native_reverse)),
-        ("string=?", Datum::native(native_string_equal_p)),
+        ("set-car!", Datum::native(native_set_car)),
+        ("set-cdr!", Datum::native(native_set_cdr)),
+        ("round", Datum::native(native_round)),
+        ("string", Datum::native(native_string)),
+        ("string=?", string_equal),
+        ("string<?", Datum::native(native_string_less_p)),
+        ("string>?", Datum::native(native_string_greater_p)),
+        ("string<=?", Datum::native(native_string_less_equal_p)),
+        ("string>=?", Datum::native(native_string_greater_equal_p)),
         ("string-append", Datum::native(native_string_append)),
+        ("string-ci=?", Datum::native(native_string_ci_equal_p)),
+        ("string-ci<?", Datum::native(native_string_ci_less_p)),
+        ("string-ci>?", Datum::native(native_string_ci_greater_p)),
+        ("string-ci<=?", Datum::native(native_string_ci_less_equal_p)),
+        ("string-ci>=?", Datum::native(native_string_ci_greater_equal_p)),
         ("string-contains", Datum::native(native_string_contains)),
+        ("string-copy", Datum::native(native_string_copy)),
+        ("string-copy!", Datum::native(native_string_copy_to)),
+        ("string-downcase", Datum::native(native_string_downcase)),
+        ("string-fill!", Datum::native(native_string_fill)),
+        ("string-foldcase", Datum::native(native_string_foldcase)),
         ("string-length", Datum::native(native_string_length)),
         ("string-prefix?", Datum::native(native_string_prefix_p)),
+        ("string-ref", Datum::native(native_string_ref)),
+        ("string-set!", Datum::native(native_string_set)),
         ("string-split", Datum::native(native_string_split)),
         ("string->list", Datum::native(native_string_to_list)),
         ("string->number", Datum::native(native_string_to_number)),
         ("string->symbol", Datum::native(native_string_to_symbol)),
+        ("string->uninterned-symbol",
+            Datum::native(native_string_to_uninterned_symbol)),
+        ("string->utf8", Datum::native(native_string_to_utf8)),
+        ("string-upcase", Datum::native(native_string_upcase)),
         ("substring", Datum::native(native_substring)),
         ("symbol->string", Datum::native(native_symbol_to_string)),
-
+        ("truncate", Datum::native(native_truncate)),
+        ("truncate/", Datum::native_values(native_truncate_divide)),
+        ("truncate-quotient", Datum::native(native_truncate_quotient)),
+        ("truncate-remainder", Datum::native(native_truncate_remainder)),
+        ("utf8->string", Datum::native(native_utf8_to_string)),
+        ("values", Datum::native_values(native_values)),
+        ("vector", Datum::native(native_vector)),
+        ("vector-append", Datum::native(native_vector_append)),
+        ("vector-copy", Datum::native(native_vector_copy)),
+        ("vector-copy!", Datum::native(native_vector_copy_to)),
+        ("vector-fill!", Datum::native(native_vector_fill)),
+        ("vector-length", Datum::native(native_vector_length)),
+        ("vector-ref", Datum::native(native_vector_ref)),
+        ("vector-set!", Datum::native(native_vector_set)),
+        ("vector->list", Datum::native(native_vector_to_list)),
+        ("write-shared", Datum::native(native_write_shared)),
+
+        ("bit-set?", Datum::native(native_bit_set_p)),
         ("boolean?", Datum::native(native_boolean_p)),
+        ("bytevector?", Datum::native(native_bytevector_p)),
         ("char?", Datum::native(native_char_p)),
+        ("char-alphabetic?", Datum::native(native_char_alphabetic_p)),
+        ("char-lower-case?", Datum::native(native_char_lower_case_p)),
+        ("char-numeric?", Datum::native(native_char_numeric_p)),
+        ("char-upper-case?", Datum::native(native_char_upper_case_p)),
+        ("char-whitespace?", Datum::native(native_char_whitespace_p)),
+        ("eof-object?", Datum::native(native_eof_object_p)),
+        ("even?", Datum::native(native_even_p)),
+        ("exact?", Datum::native(native_exact_p)),
+        ("hash-table?", Datum::native(native_hash_table_p)),
+        ("inexact?", Datum::native(native_inexact_p)),
+        ("negative?", Datum::native(native_negative_p)),
         ("number?", Datum::native(native_number_p)),
+        ("odd?", Datum::native(native_odd_p)),
         ("pair?", Datum::native(native_pair_p)),
+        ("positive?", Datum::native(native_positive_p)),
         ("procedure?", Datum::native(native_procedure_p)),
+        ("promise?", Datum::native(native_promise_p)),
+        ("record?", Datum::native(native_record_p)),
         ("string?", Datum::native(native_string_p)),
         ("symbol?", Datum::native(native_symbol_p)),
+        ("symbol-interned?", Datum::native(native_symbol_interned_p)),
         ("vector?", Datum::native(native_vector_p)),
+        ("zero?", Datum::native(native_zero_p)),
     ]
 }
 
@@ -81,6 +257,36 @@ fn special_form_begin(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Ok(instructions)
 }
 
+fn special_form_call_cc(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    expect_args!(args == 1);
+    let instructions = vec![
+        Instruction::PushValue(args[0].clone()),
+        Instruction::Evaluate(env.clone(), false),
+        Instruction::CaptureContinuation(env.clone())
+    ];
+    Ok(instructions)
+}
+
+fn special_form_call_with_values(env: Rc<RefCell<Environment>>,
+                                 args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    expect_args!(args == 2);
+    // Call the producer with no args, then pass all of the values it returns
+    // to the consumer.
+    let instructions = vec![
+        Instruction::PushValue(args[1].clone()),
+        Instruction::Evaluate(env.clone(), false),
+        Instruction::MarkValues,
+        Instruction::PushValue(list!(args[0].clone())),
+        Instruction::Evaluate(env.clone(), false),
+        Instruction::CallWithValues(env.clone())
+    ];
+    Ok(instructions)
+}
+
 fn special_form_define(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
@@ -96,14 +302,14 @@ fn special_form_define(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
                 Instruction::Define(env.clone(), name.clone(),
                     DefineType::Define),
                 // Return value is unspecified in the spec.
-                Instruction::PushValue(Datum::EmptyList)
+                Instruction::PushValue(Datum::Unspecified)
             ];
             Ok(instructions)
         },
-        Datum::Pair(ref car, ref cdr) => {
-            match **car {
+        Datum::Pair(ref pair) => {
+            let (car, formals) = pair.borrow().clone();
+            match car {
                 Datum::Symbol(ref name) => {
-                    let formals = *cdr.clone();
                     let body: Vec<_> =
                         args[1..].iter().map(|d| d.clone()).collect();
                     let mut lambda_args = vec![formals];
@@ -114,7 +320,7 @@ fn special_form_define(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
                         Instruction::Define(env.clone(), name.clone(),
                             DefineType::Define));
                     instructions.push(
-                        Instruction::PushValue(Datum::EmptyList));
+                        Instruction::PushValue(Datum::Unspecified));
                     Ok(instructions)
                 },
                 _ => runtime_error!("{}", &usage_str)
@@ -124,6 +330,151 @@ fn special_form_define(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     }
 }
 
+fn special_form_define_record_type(env: Rc<RefCell<Environment>>,
+    args: &[Datum]) -> Result<Vec<Instruction>, RuntimeError>
+{
+    let usage_str = "Usage: (define-record-type name \
+        (constructor field ...) predicate (field accessor [modifier]) ...)";
+    if args.len() < 3 { runtime_error!("{}", usage_str); }
+    let type_name = match args[0] {
+        Datum::Symbol(ref s) => s.clone(),
+        _ => runtime_error!("{}", usage_str)
+    };
+    let mut constructor = None;
+    if args[1] != Datum::Boolean(false) {
+        let parts = try_or_runtime_error!(args[1].to_vec(), "{}", usage_str);
+        let mut names = Vec::new();
+        for part in parts {
+            match part {
+                Datum::Symbol(ref s) => names.push(s.clone()),
+                _ => runtime_error!("{}", usage_str)
+            }
+        }
+        if names.is_empty() { runtime_error!("{}", usage_str); }
+        let name = names.remove(0);
+        constructor = Some((name, names));
+    }
+    let predicate = match args[2] {
+        Datum::Symbol(ref s) => s.clone(),
+        _ => runtime_error!("{}", usage_str)
+    };
+
+    // Each field spec is (field accessor [modifier]).
+    let mut fields = Vec::new();
+    let mut procedures = Vec::new();
+    for spec in &args[3..] {
+        let parts = try_or_runtime_error!(spec.to_vec(), "{}", usage_str);
+        if parts.len() < 2 || parts.len() > 3 {
+            runtime_error!("{}", usage_str);
+        }
+        let mut names = Vec::new();
+        for part in parts {
+            match part {
+                Datum::Symbol(ref s) => names.push(s.clone()),
+                _ => runtime_error!("{}", usage_str)
+            }
+        }
+        if fields.contains(&names[0]) {
+            runtime_error!("Duplicate field {} in record type {}",
+                names[0], type_name);
+        }
+        let index = fields.len();
+        fields.push(names[0].clone());
+        procedures.push((names[1].clone(), RecordProcedure::Accessor(index)));
+        if names.len() == 3 {
+            procedures.push(
+                (names[2].clone(), RecordProcedure::Modifier(index)));
+        }
+    }
+
+    let record_type = Rc::new(RecordType {name: type_name, fields: fields});
+    if let Some((name, field_names)) = constructor {
+        let mut indices = Vec::new();
+        for field in field_names.iter() {
+            match record_type.field_index(field) {
+                Some(i) => indices.push(i),
+                None => runtime_error!("Unknown field {} in constructor for {}",
+                    field, record_type.name)
+            }
+        }
+        procedures.push((name, RecordProcedure::Constructor(indices)));
+    }
+    procedures.push((predicate, RecordProcedure::Predicate));
+
+    let mut instructions = vec![
+        Instruction::PushValue(Datum::RecordType(record_type.clone())),
+        Instruction::Define(env.clone(), record_type.name.clone(),
+            DefineType::Define)
+    ];
+    for (name, procedure) in procedures {
+        instructions.push(Instruction::PushValue(
+            record_procedure(record_type.clone(), procedure)));
+        instructions.push(
+            Instruction::Define(env.clone(), name, DefineType::Define));
+    }
+    // Return value is unspecified in the spec.
+    instructions.push(Instruction::PushValue(Datum::Unspecified));
+    Ok(instructions)
+}
+
+// The procedures generated by define-record-type.
+enum RecordProcedure {
+    // Takes the values of the fields at the given indices.
+    Constructor(Vec<usize>),
+    Predicate,
+    Accessor(usize),
+    Modifier(usize)
+}
+
+fn record_procedure(record_type: Rc<RecordType>, procedure: RecordProcedure) ->
+    Datum
+{
+    match procedure {
+        RecordProcedure::Constructor(indices) => Datum::native(move |args| {
+            expect_args!(args == indices.len());
+            // Fields not set by the constructor start out as #f.
+            let mut fields = vec![Datum::Boolean(false);
+                record_type.fields.len()];
+            for (&i, arg) in indices.iter().zip(args.iter()) {
+                fields[i] = arg.clone();
+            }
+            Ok(Datum::Record(Rc::new(Record {
+                record_type: record_type.clone(),
+                fields: RefCell::new(fields)
+            })))
+        }),
+        RecordProcedure::Predicate => Datum::native(move |args| {
+            expect_args!(args == 1);
+            Ok(Datum::Boolean(match args[0] {
+                Datum::Record(ref r) => r.is_instance(&record_type),
+                _ => false
+            }))
+        }),
+        RecordProcedure::Accessor(i) => Datum::native(move |args| {
+            expect_args!(args == 1);
+            let record = try!(unwrap_record(&args[0], &record_type));
+            let value = record.fields.borrow()[i].clone();
+            Ok(value)
+        }),
+        RecordProcedure::Modifier(i) => Datum::native(move |args| {
+            expect_args!(args == 2);
+            let record = try!(unwrap_record(&args[0], &record_type));
+            record.fields.borrow_mut()[i] = args[1].clone();
+            // Return value is unspecified in the spec.
+            Ok(Datum::Unspecified)
+        })
+    }
+}
+
+fn unwrap_record(arg: &Datum, record_type: &Rc<RecordType>) ->
+    Result<Rc<Record>, RuntimeError>
+{
+    match *arg {
+        Datum::Record(ref r) if r.is_instance(record_type) => Ok(r.clone()),
+        _ => runtime_error!("Expected record of type {}", record_type.name)
+    }
+}
+
 fn special_form_define_syntax(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
@@ -134,11 +485,47 @@ fn special_form_define_syntax(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         Instruction::Evaluate(env.clone(), false),
         Instruction::Define(env.clone(), name, DefineType::DefineSyntax),
         // Return value is unspecified in the spec.
-        Instruction::PushValue(Datum::EmptyList)
+        Instruction::PushValue(Datum::Unspecified)
+    ];
+    Ok(instructions)
+}
+
+fn special_form_define_values(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    if args.len() != 2 {
+        runtime_error!("Usage: (define-values formals expression)");
+    }
+    let (names, rest_name) = try!(parse_formals(&args[0]));
+    let instructions = vec![
+        Instruction::MarkValues,
+        Instruction::PushValue(args[1].clone()),
+        Instruction::Evaluate(env.clone(), false),
+        Instruction::DefineValues(env.clone(), names, rest_name),
+        // Return value is unspecified in the spec.
+        Instruction::PushValue(Datum::Unspecified)
     ];
     Ok(instructions)
 }
 
+fn special_form_delay(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    expect_args!(args == 1);
+    let state = PromiseState::Delay(args[0].clone(), env);
+    Ok(vec![Instruction::PushValue(Datum::Promise(Rc::new(
+        Promise::new(state))))])
+}
+
+fn special_form_delay_force(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    expect_args!(args == 1);
+    let state = PromiseState::DelayForce(args[0].clone(), env);
+    Ok(vec![Instruction::PushValue(Datum::Promise(Rc::new(
+        Promise::new(state))))])
+}
+
 fn special_form_eval(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
@@ -151,6 +538,18 @@ fn special_form_eval(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Ok(instructions)
 }
 
+fn special_form_force(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    expect_args!(args == 1);
+    let instructions = vec![
+        Instruction::PushValue(args[0].clone()),
+        Instruction::Evaluate(env.clone(), false),
+        Instruction::Force
+    ];
+    Ok(instructions)
+}
+
 fn special_form_if(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
@@ -171,7 +570,7 @@ fn special_form_if(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         instructions.push(Instruction::Evaluate(env.clone(), true));
     } else {
         // Unspecified in the spec.
-        instructions.push(Instruction::PushValue(Datum::Boolean(false)));
+        instructions.push(Instruction::PushValue(Datum::Unspecified));
     }
 
     Ok(instructions)
@@ -181,14 +580,24 @@ fn special_form_lambda(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
     expect_args!(args >= 2);
-    let (arg_names, rest_name) = match args[0] {
+    let (arg_names, rest_name) = try!(parse_formals(&args[0]));
+    let body = Vec::from(&args[1..]);
+    let lambda = Datum::scheme(arg_names, rest_name, body, env.clone());
+    Ok(vec![Instruction::PushValue(lambda)])
+}
+
+// Parses lambda formals into the argument names and optional rest name.
+fn parse_formals(formals: &Datum) ->
+    Result<(Vec<Symbol>, Option<Symbol>), RuntimeError>
+{
+    let parsed = match *formals {
         Datum::Symbol(ref s) => (Vec::new(), Some(s.clone())),
         ref d @ Datum::Pair(..) => {
             let (formals, is_proper) = d.as_vec();
             let mut arg_names = Vec::new();
             for formal in formals {
                 arg_names.push(match formal {
-                    Datum::Symbol(s) => s,
+                    Datum::Symbol(ref s) => s.clone(),
                     _ => runtime_error!("Expected list or symbol list for formals")
                 });
             }
@@ -203,9 +612,76 @@ fn special_form_lambda(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         Datum::EmptyList => (Vec::new(), None),
         _ => runtime_error!("Expected symbol or symbol list for formals")
     };
-    let body = Vec::from(&args[1..]);
-    let lambda = Datum::scheme(arg_names, rest_name, body, env.clone());
-    Ok(vec![Instruction::PushValue(lambda)])
+    Ok(parsed)
+}
+
+fn special_form_let_values(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    let usage_str = "Usage: (let-values ((formals expression) ...) body ...)";
+    let bindings = try!(parse_values_bindings(args, usage_str));
+    let_values(env, bindings, &args[1..], false)
+}
+
+fn special_form_let_star_values(env: Rc<RefCell<Environment>>,
+                                args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    let usage_str = "Usage: (let*-values ((formals expression) ...) body ...)";
+    let bindings = try!(parse_values_bindings(args, usage_str));
+    let_values(env, bindings, &args[1..], true)
+}
+
+fn parse_values_bindings(args: &[Datum], usage_str: &str) ->
+    Result<Vec<(Datum, Datum)>, RuntimeError>
+{
+    if args.len() < 2 { runtime_error!("{}", usage_str); }
+    let mut bindings = Vec::new();
+    for binding in try_or_runtime_error!(args[0].to_vec(), "{}", usage_str) {
+        let mut parts =
+            try_or_runtime_error!(binding.to_vec(), "{}", usage_str);
+        if parts.len() != 2 { runtime_error!("{}", usage_str); }
+        let init = parts.pop().unwrap();
+        let formals = parts.pop().unwrap();
+        bindings.push((formals, init));
+    }
+    Ok(bindings)
+}
+
+// Binds the values of each init to its formals in a sub-environment and then
+// evaluates the body there. If sequential, each init is evaluated within the
+// bindings made before it, as with let*.
+fn let_values(env: Rc<RefCell<Environment>>, bindings: Vec<(Datum, Datum)>,
+              body: &[Datum], sequential: bool) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    let mut instructions = Vec::new();
+    let mut init_env = env.clone();
+    let mut let_env = Rc::new(RefCell::new(Environment::with_parent(env)));
+    for (i, (formals, init)) in bindings.into_iter().enumerate() {
+        if sequential && i > 0 {
+            init_env = let_env;
+            let_env = Rc::new(RefCell::new(
+                Environment::with_parent(init_env.clone())));
+        }
+        let (names, rest_name) = try!(parse_formals(&formals));
+        instructions.push(Instruction::MarkValues);
+        instructions.push(Instruction::PushValue(init));
+        instructions.push(Instruction::Evaluate(init_env.clone(), false));
+        instructions.push(
+            Instruction::DefineValues(let_env.clone(), names, rest_name));
+    }
+
+    // Add the instructions for evaluating the body within the sub-environment.
+    for (i, expr) in body.iter().enumerate() {
+        let last = i == body.len() - 1;
+        instructions.push(Instruction::PushValue(expr.clone()));
+        instructions.push(Instruction::Evaluate(let_env.clone(), last));
+        if !last {
+            instructions.push(Instruction::PopValue);
+        }
+    }
+    Ok(instructions)
 }
 
 fn special_form_letrec(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
@@ -227,7 +703,7 @@ fn special_form_letrec(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         let init = parts.remove(1);
         let variable = parts.remove(0);
         let var_name = match variable {
-            Datum::Symbol(ref s) => s.to_string(),
+            Datum::Symbol(ref s) => s.clone(),
             _ => runtime_error!("{}", &usage_str)
         };
         instructions.push(Instruction::PushValue(init));
@@ -248,6 +724,59 @@ fn special_form_letrec(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Ok(instructions)
 }
 
+fn special_form_make_parameter(env: Rc<RefCell<Environment>>,
+                               args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    if args.len() != 1 && args.len() != 2 {
+        runtime_error!("Usage: (make-parameter value [converter])");
+    }
+    let mut instructions = Vec::new();
+    for arg in args {
+        instructions.push(Instruction::PushValue(arg.clone()));
+        instructions.push(Instruction::Evaluate(env.clone(), false));
+    }
+    // The initial value is passed through the converter too.
+    instructions.push(Instruction::MakeParameter(args.len() == 2));
+    instructions.push(Instruction::ConvertParameter(env.clone()));
+    instructions.push(Instruction::InitParameter);
+    Ok(instructions)
+}
+
+fn special_form_parameterize(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    let usage_str = "Usage: (parameterize ((parameter value) ...) body ...)";
+    if args.len() < 2 { runtime_error!("{}", usage_str); }
+
+    // Evaluate and convert all of the values before binding any of them.
+    let mut instructions = Vec::new();
+    let bindings = try_or_runtime_error!(args[0].to_vec(), "{}", usage_str);
+    for binding in bindings.iter() {
+        let parts = try_or_runtime_error!(binding.to_vec(), "{}", usage_str);
+        if parts.len() != 2 { runtime_error!("{}", usage_str); }
+        for part in parts {
+            instructions.push(Instruction::PushValue(part));
+            instructions.push(Instruction::Evaluate(env.clone(), false));
+        }
+        instructions.push(Instruction::ConvertParameter(env.clone()));
+    }
+    instructions.push(Instruction::Parameterize(bindings.len()));
+
+    // The body isn't in tail position, since the old values must be restored
+    // once it returns.
+    let body_env = Rc::new(RefCell::new(Environment::with_parent(env)));
+    for (i, expr) in args.iter().skip(1).enumerate() {
+        instructions.push(Instruction::PushValue(expr.clone()));
+        instructions.push(Instruction::Evaluate(body_env.clone(), false));
+        if i < args.len() - 2 {
+            instructions.push(Instruction::PopValue);
+        }
+    }
+    instructions.push(Instruction::Unparameterize(bindings.len()));
+    Ok(instructions)
+}
+
 fn special_form_quote(_: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
@@ -255,6 +784,16 @@ fn special_form_quote(_: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Ok(vec![Instruction::PushValue(args[0].clone())])
 }
 
+fn special_form_receive(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    if args.len() < 3 {
+        runtime_error!("Usage: (receive formals expression body ...)");
+    }
+    let bindings = vec![(args[0].clone(), args[1].clone())];
+    let_values(env, bindings, &args[2..], false)
+}
+
 fn special_form_set(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>
 {
@@ -265,7 +804,7 @@ fn special_form_set(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         Instruction::Evaluate(env.clone(), false),
         Instruction::Define(env.clone(), name.clone(), DefineType::Set),
         // Return value is unspecified in the spec.
-        Instruction::PushValue(Datum::EmptyList)
+        Instruction::PushValue(Datum::Unspecified)
     ];
     Ok(instructions)
 }
@@ -285,7 +824,7 @@ fn special_form_syntax_rules(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
             _ => runtime_error!("{}", &usage_str)
         });
     }
-    if keywords.contains(&String::from("...")) {
+    if keywords.contains(&Symbol::intern("...")) {
         runtime_error!("Ellipses (...) cannot be in the keywords list");
     }
 
@@ -297,9 +836,9 @@ fn special_form_syntax_rules(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         let template = parts.remove(1);
         let pattern_datum = parts.remove(0);
         let pattern = match pattern_datum {
-            Datum::Pair(ref car, ref cdr) => {
-                match **car {
-                    Datum::Symbol(_) => *cdr.clone(),
+            Datum::Pair(ref pair) => {
+                match *pair.borrow() {
+                    (Datum::Symbol(_), ref cdr) => cdr.clone(),
                     _ => runtime_error!("First element in a pattern must be the macro identifier")
                 }
             }
@@ -332,9 +871,9 @@ fn special_form_syntax_rules(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
         // Verify that a raw un-expanded macro call has been passed.
         if args.len() != 1 { runtime_error!("Expected 1 arg"); }
         let (macro_name, input) = match args[0] {
-            Datum::Pair(ref car, ref cdr) => {
-                match **car {
-                    Datum::Symbol(ref s) => (s.clone(), *cdr.clone()),
+            Datum::Pair(ref pair) => {
+                match *pair.borrow() {
+                    (Datum::Symbol(ref s), ref cdr) => (s.clone(), cdr.clone()),
                     _ => runtime_error!("First element in a pattern must be the macro identifier")
                 }
             },
@@ -357,8 +896,8 @@ fn special_form_syntax_rules(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
                         let mut new_name = template_sym.clone();
                         let mut temp_index = 1;
                         while let Some(_) = env.borrow().get(&new_name) {
-                            new_name = format!("{}_hygienic_{}",
-                                template_sym, temp_index);
+                            new_name = Symbol::intern(&format!(
+                                "{}_hygienic_{}", template_sym, temp_index));
                             temp_index += 1;
                         }
                         name_mappings.insert(template_sym.clone(), new_name);
@@ -398,7 +937,7 @@ fn special_form_syntax_rules(env: Rc<RefCell<Environment>>, args: &[Datum]) ->
 }
 
 // Renames symbols in the template according to the given mappings.
-fn rename_template(template: &Datum, mappings: &HashMap<String, String>) ->
+fn rename_template(template: &Datum, mappings: &HashMap<Symbol, Symbol>) ->
     Datum
 {
     match template {
@@ -408,25 +947,30 @@ fn rename_template(template: &Datum, mappings: &HashMap<String, String>) ->
                 None => template.clone()
             }
         },
-        &Datum::Pair(ref car, ref cdr) =>
-            Datum::pair(rename_template(&car, mappings),
-                rename_template(&cdr, mappings)),
+        &Datum::Pair(ref pair) => {
+            let (ref car, ref cdr) = *pair.borrow();
+            Datum::pair(rename_template(car, mappings),
+                rename_template(cdr, mappings))
+        },
+        &Datum::Vector(ref v) =>
+            Datum::vector(v.borrow().iter()
+                .map(|d| rename_template(d, mappings)).collect()),
         _ => template.clone()
     }
 }
 
 // Returns the names of all pattern variables if successful.
 // Duplicates are not allowed.
-fn verify_pattern(pattern: &Datum, keywords: &[String]) ->
-    Result<HashSet<String>, RuntimeError>
+fn verify_pattern(pattern: &Datum, keywords: &[Symbol]) ->
+    Result<HashSet<Symbol>, RuntimeError>
 {
     let mut variables = HashSet::new();
     try!(verify_pattern_helper(pattern, keywords, true, &mut variables));
     Ok(variables)
 }
 
-fn verify_pattern_helper(pattern: &Datum, keywords: &[String], list_begin: bool,
-    variables: &mut HashSet<String>) -> Result<(), RuntimeError>
+fn verify_pattern_helper(pattern: &Datum, keywords: &[Symbol], list_begin: bool,
+    variables: &mut HashSet<Symbol>) -> Result<(), RuntimeError>
 {
     match pattern {
         &Datum::Symbol(ref s) if !keywords.contains(s) && s != "..." => {
@@ -436,12 +980,13 @@ fn verify_pattern_helper(pattern: &Datum, keywords: &[String], list_begin: bool,
             variables.insert(s.clone());
             Ok(())
         },
-        &Datum::Pair(ref car, ref cdr) => {
+        &Datum::Pair(ref pair) => {
+            let (ref car, ref cdr) = *pair.borrow();
             // Check for ellipses. They should only be found at the
             // end of a list following a pattern.
-            match **car {
+            match *car {
                 Datum::Symbol(ref s) if s == "..." => {
-                    let list_end = match **cdr {
+                    let list_end = match *cdr {
                         Datum::EmptyList => true,
                         _ => false
                     };
@@ -458,19 +1003,23 @@ fn verify_pattern_helper(pattern: &Datum, keywords: &[String], list_begin: bool,
             try!(verify_pattern_helper(cdr, keywords, false, variables));
             Ok(())
         },
+        // Vector patterns follow the same rules as list patterns.
+        &Datum::Vector(ref v) =>
+            verify_pattern_helper(&Datum::list(v.borrow().clone()), keywords,
+                true, variables),
         _ => Ok(())
     }
 }
 
 // Returns the symbols in the template if successful.
-fn verify_template(template: &Datum) -> Result<HashSet<String>, RuntimeError> {
+fn verify_template(template: &Datum) -> Result<HashSet<Symbol>, RuntimeError> {
     let mut symbols = HashSet::new();
     try!(verify_template_helper(template, true, &mut symbols));
     Ok(symbols)
 }
 
 fn verify_template_helper(template: &Datum, list_begin: bool,
-    symbols: &mut HashSet<String>) -> Result<(), RuntimeError>
+    symbols: &mut HashSet<Symbol>) -> Result<(), RuntimeError>
 {
     match template {
         &Datum::Symbol(ref s) if s != "..." => {
@@ -479,9 +1028,10 @@ fn verify_template_helper(template: &Datum, list_begin: bool,
             }
             Ok(())
         },
-        &Datum::Pair(ref car, ref cdr) => {
+        &Datum::Pair(ref pair) => {
+            let (ref car, ref cdr) = *pair.borrow();
             // Check for ellipses- they should only be following a pattern.
-            match **car {
+            match *car {
                 Datum::Symbol(ref s) if s == "..." => {
                     let follows_pattern = !list_begin;
                     if !follows_pattern {
@@ -496,13 +1046,16 @@ fn verify_template_helper(template: &Datum, list_begin: bool,
             try!(verify_template_helper(cdr, false, symbols));
             Ok(())
         },
+        &Datum::Vector(ref v) =>
+            verify_template_helper(&Datum::list(v.borrow().clone()), true,
+                symbols),
         _ => Ok(())
     }
 }
 
 // Attempts to match the input to the given pattern. If successful,
 // an environment of the pattern variables is returned.
-fn match_pattern(pattern: &Datum, input: &Datum, keywords: &[String]) ->
+fn match_pattern(pattern: &Datum, input: &Datum, keywords: &[Symbol]) ->
     Option<Environment>
 {
     let mut env = Environment::new();
@@ -513,7 +1066,7 @@ fn match_pattern(pattern: &Datum, input: &Datum, keywords: &[String]) ->
     }
 }
 
-fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[String],
+fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[Symbol],
     env: &mut Environment) -> bool
 {
     match (pattern, input) {
@@ -529,14 +1082,18 @@ fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[String],
             env.define(s, inp.clone());
             true
         },
-        // TODO: Implement this.
-        (&Datum::Vector(..), _) => unimplemented!(),
+        // Vectors are matched element-wise as if they were lists.
+        (&Datum::Vector(ref pv), &Datum::Vector(ref iv)) =>
+            match_pattern_helper(&Datum::list(pv.borrow().clone()),
+                &Datum::list(iv.borrow().clone()), keywords, env),
+        (&Datum::Vector(..), _) => false,
         (&Datum::Procedure(..), _) => false,
         (&Datum::SyntaxRule(..), _) => false,
-        (&Datum::Pair(ref pcar, ref pcdr), inp @ _) => {
-            let zero_or_more = match **pcdr {
-                Datum::Pair(ref next, _) => {
-                    match **next {
+        (&Datum::Pair(ref pattern_pair), inp @ _) => {
+            let (ref pcar, ref pcdr) = *pattern_pair.borrow();
+            let zero_or_more = match *pcdr {
+                Datum::Pair(ref next) => {
+                    match next.borrow().0 {
                         Datum::Symbol(ref s) if s == "..." => true,
                         _ => false
                     }
@@ -545,14 +1102,14 @@ fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[String],
             };
             if zero_or_more {
                 // Match as long as possible.
-                let mut current = inp;
+                let mut current = inp.clone();
                 let mut at_least_one_found = false;
                 let mut to_reverse = HashSet::new();
                 loop {
                     // Make sure the current is part of a list.
                     let (element, next) = match current {
-                        &Datum::Pair(ref ccar, ref ccdr) => (ccar, ccdr),
-                        &Datum::EmptyList => break,
+                        Datum::Pair(ref pair) => pair.borrow().clone(),
+                        Datum::EmptyList => break,
                         // Not a list so doesn't match.
                         _ => return false
                     };
@@ -578,7 +1135,7 @@ fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[String],
                     }
 
                     // Move to the next element.
-                    current = &**next;
+                    current = next;
                     at_least_one_found = true;
                 }
 
@@ -597,9 +1154,10 @@ fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[String],
             } else {
                 // Continue matching one at a time.
                 match inp {
-                    &Datum::Pair(ref icar, ref icdr) => {
+                    &Datum::Pair(ref input_pair) => {
+                        let (ref icar, ref icdr) = *input_pair.borrow();
                         match_pattern_helper(pcar, icar, keywords, env) &&
-                            match_pattern_helper(pcdr, icdr, keywords, env)
+                        match_pattern_helper(pcdr, icdr, keywords, env)
                     },
                     _ => false
                 }
@@ -609,38 +1167,50 @@ fn match_pattern_helper(pattern: &Datum, input: &Datum, keywords: &[String],
     }
 }
 
-fn add_empty_matching(pattern: &Datum, keywords: &[String],
+fn add_empty_matching(pattern: &Datum, keywords: &[Symbol],
     env: &mut Environment)
 {
     match pattern {
         &Datum::Symbol(ref s) if !keywords.contains(s) => {
             env.define(s, Datum::EmptyList);
         },
-        &Datum::Pair(ref car, ref cdr) => {
+        &Datum::Pair(ref pair) => {
+            let (ref car, ref cdr) = *pair.borrow();
             add_empty_matching(car, keywords, env);
             add_empty_matching(cdr, keywords, env);
         },
+        &Datum::Vector(ref v) => {
+            for d in v.borrow().iter() {
+                add_empty_matching(d, keywords, env);
+            }
+        },
         _ => ()
     }
 }
 
-fn get_variables(template: &Datum, var_env: &Environment) -> HashSet<String> {
+fn get_variables(template: &Datum, var_env: &Environment) -> HashSet<Symbol> {
     let mut variables = HashSet::new();
     get_variables_helper(template, var_env, &mut variables);
     variables
 }
 
 fn get_variables_helper(template: &Datum, var_env: &Environment,
-    variables: &mut HashSet<String>)
+    variables: &mut HashSet<Symbol>)
 {
     match template {
         &Datum::Symbol(ref s) if var_env.contains(s) && s != "..." => {
             variables.insert(s.clone());
         },
-        &Datum::Pair(ref car, ref cdr) => {
+        &Datum::Pair(ref pair) => {
+            let (ref car, ref cdr) = *pair.borrow();
             get_variables_helper(car, var_env, variables);
             get_variables_helper(cdr, var_env, variables);
         },
+        &Datum::Vector(ref v) => {
+            for d in v.borrow().iter() {
+                get_variables_helper(d, var_env, variables);
+            }
+        },
         _ => ()
     }
 }
@@ -652,12 +1222,13 @@ fn apply_template(template: &Datum, var_env: &Environment) ->
         // Handle variable substitution.
         &Datum::Symbol(ref s) if var_env.contains(s) =>
             Ok(var_env.get(s).unwrap()),
-        &Datum::Pair(ref car, ref cdr) => {
-            let (zero_or_more, after) = match **cdr {
-                Datum::Pair(ref next, ref after) => {
-                    match **next {
-                        Datum::Symbol(ref s) if s == "..." =>
-                            (true, Some(after)),
+        &Datum::Pair(ref pair) => {
+            let (ref car, ref cdr) = *pair.borrow();
+            let (zero_or_more, after) = match *cdr {
+                Datum::Pair(ref next) => {
+                    match *next.borrow() {
+                        (Datum::Symbol(ref s), ref after) if s == "..." =>
+                            (true, Some(after.clone())),
                         _ => (false, None)
                     }
                 },
@@ -670,7 +1241,7 @@ fn apply_template(template: &Datum, var_env: &Environment) ->
                 if variables.len() == 0 {
                     runtime_error!("Expected variables before ellipses");
                 }
-                let vectors: Vec<(String, Vec<Datum>)> = variables.iter()
+                let vectors: Vec<(Symbol, Vec<Datum>)> = variables.iter()
                     .map(|v| (v.clone(), var_env.get(v).unwrap().as_vec().0))
                     .collect();
                 let iterations = vectors.iter()
@@ -681,23 +1252,24 @@ fn apply_template(template: &Datum, var_env: &Environment) ->
                 for i in 0..iterations {
                     let mut sub_env = Environment::new();
                     for &(ref var, ref values) in vectors.iter() {
-                        sub_env.define(&var, values[i].clone());
+                        sub_env.define(var, values[i].clone());
                     }
                     let result = try!(apply_template(car, &sub_env));
                     reversed = Datum::pair(result, reversed);
                 }
 
                 // Recursively apply the template to the rest.
-                let mut result = try!(apply_template(after.unwrap(), var_env));
+                let mut result = try!(apply_template(&after.unwrap(), var_env));
 
                 // Unreverse the list as it is attached to the rest.
-                let mut current = &reversed;
+                let mut current = reversed;
                 loop {
                     current = match current {
-                        &Datum::EmptyList => break,
-                        &Datum::Pair(ref a, ref b) => {
-                            result = Datum::pair(*a.clone(), result);
-                            &*b
+                        Datum::EmptyList => break,
+                        Datum::Pair(ref pair) => {
+                            let pair = pair.borrow();
+                            result = Datum::pair(pair.0.clone(), result);
+                            pair.1.clone()
                         },
                         _ => panic!("bug in apply_template")
                     };
@@ -709,14 +1281,20 @@ fn apply_template(template: &Datum, var_env: &Environment) ->
                     try!(apply_template(cdr, var_env))))
             }
         },
+        // Apply vector templates as lists so ellipses are handled the same.
+        &Datum::Vector(ref v) => {
+            let result = try!(apply_template(&Datum::list(v.borrow().clone()),
+                var_env));
+            Ok(Datum::vector(result.as_vec().0))
+        },
         t @ _ => Ok(t.clone())
     }
 }
 
 fn native_add(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    let mut sum = 0;
+    let mut sum = Number::Integer(0);
     for a in args {
-        sum += try_unwrap_arg!(*a => i64);
+        sum = sum + try_unwrap_arg!(*a => Number);
     }
 
     Ok(Datum::Number(sum))
@@ -725,9 +1303,9 @@ fn native_add(args: &[Datum]) -> Result<Datum, RuntimeError> {
 fn native_subtract(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args >= 1);
 
-    let mut difference = 0;
+    let mut difference = Number::Integer(0);
     for (i, a) in args.iter().enumerate() {
-        let n = try_unwrap_arg!(*a => i64);
+        let n = try_unwrap_arg!(*a => Number);
         difference = if i == 0 { n } else { difference - n };
     }
 
@@ -736,6 +1314,23 @@ fn native_subtract(args: &[Datum]) -> Result<Datum, RuntimeError> {
     else { Ok(Datum::Number(difference)) }
 }
 
+fn native_divide(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args >= 1);
+
+    let mut quotient = Number::Integer(1);
+    for (i, a) in args.iter().enumerate() {
+        let n = try_unwrap_arg!(*a => Number);
+        // Handle unary case by dividing 1 by the argument.
+        if i == 0 && args.len() > 1 { quotient = n; continue; }
+        quotient = match quotient.checked_div(n) {
+            Some(q) => q,
+            None => runtime_error!("Division by zero")
+        };
+    }
+
+    Ok(Datum::Number(quotient))
+}
+
 fn native_append(args: &[Datum]) -> Result<Datum, RuntimeError> {
     if args.len() == 0 { return Ok(Datum::EmptyList); }
     let mut result = vec![];
@@ -752,7 +1347,7 @@ fn native_append(args: &[Datum]) -> Result<Datum, RuntimeError> {
 fn native_car(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 1);
     match args[0] {
-        Datum::Pair(ref car, _) => Ok(*car.clone()),
+        Datum::Pair(ref pair) => Ok(pair.borrow().0.clone()),
         _ => runtime_error!("Expected pair")
     }
 }
@@ -760,14 +1355,34 @@ fn native_car(args: &[Datum]) -> Result<Datum, RuntimeError> {
 fn native_cdr(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 1);
     match args[0] {
-        Datum::Pair(_, ref cdr) => Ok(*cdr.clone()),
+        Datum::Pair(ref pair) => Ok(pair.borrow().1.clone()),
         _ => runtime_error!("Expected pair")
     }
 }
 
 fn native_cons(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 2);
-    Ok(Datum::Pair(Box::new(args[0].clone()), Box::new(args[1].clone())))
+    Ok(Datum::pair(args[0].clone(), args[1].clone()))
+}
+
+fn native_set_car(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    match args[0] {
+        Datum::Pair(ref pair) => pair.borrow_mut().0 = args[1].clone(),
+        _ => runtime_error!("Expected pair")
+    }
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_set_cdr(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    match args[0] {
+        Datum::Pair(ref pair) => pair.borrow_mut().1 = args[1].clone(),
+        _ => runtime_error!("Expected pair")
+    }
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
 }
 
 fn native_equals(args: &[Datum]) -> Result<Datum, RuntimeError> {
@@ -775,216 +1390,805 @@ fn native_equals(args: &[Datum]) -> Result<Datum, RuntimeError> {
         return Ok(Datum::Boolean(true));
     }
 
-    let first = match args[0] {
-        Datum::Number(n) => n,
-        _ => runtime_error!("Expected number")
-    };
+    let first = try_unwrap_arg!(args[0] => Number);
 
     let mut res = true;
     for a in &args[1..] {
-        res = res && (try_unwrap_arg!(*a => i64) == first);
+        res = res && try_unwrap_arg!(*a => Number).num_eq(&first);
     }
 
     Ok(Datum::Boolean(res))
 }
 
-fn native_multiply(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    let mut product = 1;
-    for a in args {
-        product *= try_unwrap_arg!(*a => i64);
+fn native_number_to_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() != 1 && args.len() != 2 {
+        runtime_error!("Usage: (number->string number [radix])");
+    }
+    let n = try_unwrap_arg!(args[0] => Number);
+    let radix = if args.len() == 2 { try!(get_radix(&args[1])) } else { 10 };
+    match n.to_string_radix(radix) {
+        Some(s) => Ok(Datum::string(s)),
+        None => runtime_error!("Cannot format {} in radix {}", n, radix)
     }
-
-    Ok(Datum::Number(product))
 }
 
-fn native_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 2);
-
-    match (&args[0], &args[1]) {
-        (&Datum::Boolean(ref b1), &Datum::Boolean(ref b2)) =>
-            Ok(Datum::Boolean(b1 == b2)),
-        (&Datum::Symbol(ref s1), &Datum::Symbol(ref s2)) =>
-            Ok(Datum::Boolean(s1 == s2)),
-        (&Datum::Number(ref n1), &Datum::Number(ref n2)) =>
-            Ok(Datum::Boolean(n1 == n2)),
-        (&Datum::Character(ref c1), &Datum::Character(ref c2)) =>
-            Ok(Datum::Boolean(c1 == c2)),
-        (&Datum::Ext(ref e1), &Datum::Ext(ref e2)) =>
-            Ok(Datum::Boolean(e1 == e2)),
-        (&Datum::EmptyList, &Datum::EmptyList) => Ok(Datum::Boolean(true)),
-        (&Datum::Procedure(ref p1), &Datum::Procedure(ref p2)) => {
-            match (p1, p2) {
-                // Note: compare pointers here.
-                (&Procedure::SpecialForm(ref s1),
-                    &Procedure::SpecialForm(ref s2)) =>
-                        Ok(Datum::Boolean(&(**s1) as *const _ ==
-                                          &(**s2) as *const _)),
-                (&Procedure::Native(ref n1),
-                    &Procedure::Native(ref n2)) =>
-                        Ok(Datum::Boolean(&(**n1) as *const _ ==
-                                          &(**n2) as *const _)),
-                (&Procedure::Scheme(ref s1),
-                    &Procedure::Scheme(ref s2)) =>
-                        Ok(Datum::Boolean(&(**s1) as *const _ ==
-                                          &(**s2) as *const _)),
-                _ => Ok(Datum::Boolean(false))
-            }
-        },
-        (&Datum::Pair(ref car1, ref cdr1),&Datum::Pair(ref car2, ref cdr2)) => {
-            let car_result = try!(
-                native_equal_p(&vec![*car1.clone(), *car2.clone()]));
-            let cdr_result = try!(
-                native_equal_p(&vec![*cdr1.clone(), *cdr2.clone()]));
-            Ok(Datum::Boolean(match (car_result, cdr_result) {
-                (Datum::Boolean(false), _) => false,
-                (_, Datum::Boolean(false)) => false,
-                _ => true
-            }))
-        },
-        (&Datum::Vector(ref v1), &Datum::Vector(ref v2)) => {
-            if v1.borrow().len() != v2.borrow().len() {
-                return Ok(Datum::Boolean(false));
-            }
-            for (e1, e2) in v1.borrow().iter().zip(v2.borrow().iter()) {
-                match try!(native_equal_p(&vec![e1.clone(), e2.clone()])) {
-                    d @ Datum::Boolean(false) => return Ok(d),
-                    _ => ()
-                }
-            }
-            Ok(Datum::Boolean(true))
-        },
-        (&Datum::String(ref s1), &Datum::String(ref s2)) =>
-            Ok(Datum::Boolean(s1 == s2)),
-        _ => Ok(Datum::Boolean(false))
+// Radixes supported by number->string and string->number.
+fn get_radix(arg: &Datum) -> Result<u32, RuntimeError> {
+    match try_unwrap_arg!(*arg => i64) {
+        r @ 2 | r @ 8 | r @ 10 | r @ 16 => Ok(r as u32),
+        r => runtime_error!("Radix must be 2, 8, 10 or 16; got {}", r)
     }
 }
 
-fn native_eqv_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 2);
+fn native_less(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_numbers(args, |o| o == Ordering::Less)
+}
 
-    match (&args[0], &args[1]) {
-        (&Datum::Boolean(ref b1), &Datum::Boolean(ref b2)) =>
-            Ok(Datum::Boolean(b1 == b2)),
-        (&Datum::Symbol(ref s1), &Datum::Symbol(ref s2)) =>
-            Ok(Datum::Boolean(s1 == s2)),
-        (&Datum::Number(ref n1), &Datum::Number(ref n2)) =>
-            Ok(Datum::Boolean(n1 == n2)),
-        (&Datum::Character(ref c1), &Datum::Character(ref c2)) =>
-            Ok(Datum::Boolean(c1 == c2)),
-        (&Datum::EmptyList, &Datum::EmptyList) => Ok(Datum::Boolean(true)),
-        (&Datum::Procedure(ref p1), &Datum::Procedure(ref p2)) => {
-            match (p1, p2) {
-                // Note: compare pointers here.
-                (&Procedure::SpecialForm(ref s1),
-                    &Procedure::SpecialForm(ref s2)) =>
-                        Ok(Datum::Boolean(&(**s1) as *const _ ==
-                                          &(**s2) as *const _)),
-                (&Procedure::Native(ref n1),
-                    &Procedure::Native(ref n2)) =>
-                        Ok(Datum::Boolean(&(**n1) as *const _ ==
-                                          &(**n2) as *const _)),
-                (&Procedure::Scheme(ref s1),
-                    &Procedure::Scheme(ref s2)) =>
-                        Ok(Datum::Boolean(&(**s1) as *const _ ==
-                                          &(**s2) as *const _)),
-                _ => Ok(Datum::Boolean(false))
-            }
-        },
-        (&Datum::Vector(ref v1), &Datum::Vector(ref v2)) =>
-            Ok(Datum::Boolean(&(**v1) as *const _ ==
-                              &(**v2) as *const _)),
-        (&Datum::Pair(..), &Datum::Pair(..)) | 
-        (&Datum::String(..), &Datum::String(..)) =>
-            Ok(Datum::Boolean(false)),
-        _ => Ok(Datum::Boolean(false))
-    }
+fn native_greater(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_numbers(args, |o| o == Ordering::Greater)
 }
 
-fn native_hash_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 2);
-    let h = try_unwrap_arg!(args[0] =>
-                            Rc<RefCell<HashMap<Datum, Datum>>>);
-
-    // Make sure the key can be hashed.
-    match args[1] {
-        // TODO: Support Ext for hashing.
-        Datum::Procedure(_) | Datum::SyntaxRule(..) | Datum::Ext(..) =>
-            return Ok(Datum::Boolean(false)),
-        _ => ()
-    }
+fn native_less_equal(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_numbers(args, |o| o != Ordering::Greater)
+}
 
-    match h.borrow().get(&args[1]) {
-        Some(d) => Ok(d.clone()),
-        None => Ok(Datum::Boolean(false))
-    }
+fn native_greater_equal(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_numbers(args, |o| o != Ordering::Less)
 }
 
-fn native_hash_set(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 3);
-    let h = try_unwrap_arg!(args[0] =>
-                            Rc<RefCell<HashMap<Datum, Datum>>>);
-
-    // Make sure the key can be hashed.
-    match args[1] {
-        // TODO: Support Ext for hashing.
-        Datum::Procedure(_) | Datum::SyntaxRule(..) | Datum::Ext(..) =>
-            runtime_error!("Hashing not supported for {}", args[1]),
-        _ => ()
+// Checks that the ordering of each adjacent pair of numbers satisfies the
+// predicate. Any comparison involving a NaN is false.
+fn compare_numbers<F: Fn(Ordering) -> bool>(args: &[Datum], pred: F) ->
+    Result<Datum, RuntimeError>
+{
+    expect_args!(args >= 1);
+    let mut numbers = Vec::new();
+    for a in args {
+        numbers.push(try_unwrap_arg!(*a => Number));
     }
 
-    h.borrow_mut().insert(args[1].clone(), args[2].clone());
-    Ok(args[2].clone())
+    let res = numbers.windows(2).all(|pair| {
+        match pair[0].compare(&pair[1]) {
+            Some(o) => pred(o),
+            None => false
+        }
+    });
+    Ok(Datum::Boolean(res))
 }
 
-fn native_length(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 1);
-    Ok(Datum::Number(try!(args[0].to_vec()).len() as i64))
+fn native_char_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, false, |o| o == Ordering::Equal)
 }
 
-fn native_list(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    let elements: Vec<_> = args.iter().map(|e| e.clone()).collect();
-    Ok(Datum::list(elements))
+fn native_char_less_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, false, |o| o == Ordering::Less)
 }
 
-fn native_list_to_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 1);
-    let list = try!(args[0].to_vec());
-    let mut string = String::new();
-    for d in list {
-        let ch = try_unwrap_arg!(d => char);
-        string.push(ch);
-    }
-    Ok(Datum::String(string))
+fn native_char_greater_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, false, |o| o == Ordering::Greater)
 }
 
-fn native_make_hash_table(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 0);
-    Ok(Datum::ext(Rc::new(RefCell::new(
-        HashMap::<Datum, Datum>::new())), "hash-table"))
+fn native_char_less_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, false, |o| o != Ordering::Greater)
 }
 
-fn native_null_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 1);
-    match args[0] {
-        Datum::EmptyList => Ok(Datum::Boolean(true)),
-        _ => Ok(Datum::Boolean(false))
-    }
+fn native_char_greater_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, false, |o| o != Ordering::Less)
 }
 
-fn native_string_append(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 2);
-    let mut s1 = try_unwrap_arg!(args[0] => String).clone();
-    let s2 = try_unwrap_arg!(args[1] => String);
-    s1.push_str(s2);
-    Ok(Datum::String(s1))
+fn native_char_ci_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, true, |o| o == Ordering::Equal)
 }
 
-fn native_string_contains(args: &[Datum]) -> Result<Datum, RuntimeError> {
+fn native_char_ci_less_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, true, |o| o == Ordering::Less)
+}
+
+fn native_char_ci_greater_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, true, |o| o == Ordering::Greater)
+}
+
+fn native_char_ci_less_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_chars(args, true, |o| o != Ordering::Greater)
+}
+
+fn native_char_ci_greater_equal_p(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    compare_chars(args, true, |o| o != Ordering::Less)
+}
+
+// Checks that the ordering of each adjacent pair of characters satisfies
+// the predicate, optionally comparing case-folded characters.
+fn compare_chars<F: Fn(Ordering) -> bool>(args: &[Datum], fold: bool,
+    pred: F) -> Result<Datum, RuntimeError>
+{
+    expect_args!(args >= 1);
+    let mut chars = Vec::new();
+    for a in args {
+        let c = try_unwrap_arg!(*a => char);
+        chars.push(if fold { foldcase(c) } else { c });
+    }
+
+    let res = chars.windows(2).all(|pair| pred(pair[0].cmp(&pair[1])));
+    Ok(Datum::Boolean(res))
+}
+
+fn native_char_downcase(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Character(downcase(try_unwrap_arg!(args[0] => char))))
+}
+
+fn native_char_foldcase(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Character(foldcase(try_unwrap_arg!(args[0] => char))))
+}
+
+fn native_char_upcase(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Character(upcase(try_unwrap_arg!(args[0] => char))))
+}
+
+// Case conversions for single characters. Characters without a single
+// character mapping are left as-is.
+fn upcase(c: char) -> char {
+    single_char(c.to_uppercase()).unwrap_or(c)
+}
+
+fn downcase(c: char) -> char {
+    single_char(c.to_lowercase()).unwrap_or(c)
+}
+
+fn foldcase(c: char) -> char {
+    downcase(upcase(c))
+}
+
+fn single_char<I: Iterator<Item=char>>(mut iter: I) -> Option<char> {
+    match (iter.next(), iter.next()) {
+        (Some(c), None) => Some(c),
+        _ => None
+    }
+}
+
+fn native_char_to_integer(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let c = try_unwrap_arg!(args[0] => char);
+    Ok(Datum::integer(c as i64))
+}
+
+fn native_integer_to_char(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try_unwrap_arg!(args[0] => i64);
+    let c = if n >= 0 && n <= u32::MAX as i64 { char::from_u32(n as u32) }
+        else { None };
+    match c {
+        Some(c) => Ok(Datum::Character(c)),
+        None => runtime_error!("Not a Unicode scalar value: {}", n)
+    }
+}
+
+fn native_digit_value(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let c = try_unwrap_arg!(args[0] => char);
+    match decimal_digit_value(c) {
+        Some(d) => Ok(Datum::integer(d as i64)),
+        None => Ok(Datum::Boolean(false))
+    }
+}
+
+// The zero of each run of ten decimal digits (general category Nd) in
+// Unicode 15.0, in order.
+const DECIMAL_DIGIT_ZEROS: &'static [u32] = &[
+    0x30, 0x660, 0x6F0, 0x7C0, 0x966, 0x9E6, 0xA66, 0xAE6, 0xB66, 0xBE6,
+    0xC66, 0xCE6, 0xD66, 0xDE6, 0xE50, 0xED0, 0xF20, 0x1040, 0x1090, 0x17E0,
+    0x1810, 0x1946, 0x19D0, 0x1A80, 0x1A90, 0x1B50, 0x1BB0, 0x1C40, 0x1C50,
+    0xA620, 0xA8D0, 0xA900, 0xA9D0, 0xA9F0, 0xAA50, 0xABF0, 0xFF10, 0x104A0,
+    0x10D30, 0x11066, 0x110F0, 0x11136, 0x111D0, 0x112F0, 0x11450, 0x114D0,
+    0x11650, 0x116C0, 0x11730, 0x118E0, 0x11950, 0x11C50, 0x11D50, 0x11DA0,
+    0x11F50, 0x16A60, 0x16AC0, 0x16B50, 0x1D7CE, 0x1D7D8, 0x1D7E2, 0x1D7EC,
+    0x1D7F6, 0x1E140, 0x1E2F0, 0x1E4F0, 0x1E950, 0x1FBF0
+];
+
+// Returns the value of a decimal digit character.
+fn decimal_digit_value(c: char) -> Option<u32> {
+    let c = c as u32;
+    let zero = match DECIMAL_DIGIT_ZEROS.binary_search(&c) {
+        Ok(i) => DECIMAL_DIGIT_ZEROS[i],
+        Err(0) => return None,
+        Err(i) => DECIMAL_DIGIT_ZEROS[i - 1]
+    };
+    if c - zero < 10 { Some(c - zero) } else { None }
+}
+
+fn native_abs(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Number(try_unwrap_arg!(args[0] => Number).abs()))
+}
+
+fn native_max(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    extremum(args, Ordering::Greater)
+}
+
+fn native_min(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    extremum(args, Ordering::Less)
+}
+
+// Finds the number that compares as the given ordering against all others.
+// The result is inexact if any of the numbers are.
+fn extremum(args: &[Datum], ordering: Ordering) -> Result<Datum, RuntimeError> {
+    expect_args!(args >= 1);
+    let mut result = try_unwrap_arg!(args[0] => Number);
+    let mut exact = result.is_exact();
+    for a in &args[1..] {
+        let n = try_unwrap_arg!(*a => Number);
+        exact = exact && n.is_exact();
+        match n.compare(&result) {
+            Some(o) if o == ordering => result = n,
+            // NaNs are contagious.
+            None if result.sign().is_some() => result = n,
+            _ => ()
+        }
+    }
+
+    if exact { Ok(Datum::Number(result)) }
+    else { Ok(Datum::Number(result.to_inexact())) }
+}
+
+fn native_gcd(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Number::Integer(0);
+    for a in args {
+        result = result.gcd(try!(unwrap_integer(a)));
+    }
+    Ok(Datum::Number(result))
+}
+
+fn native_lcm(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Number::Integer(1);
+    for a in args {
+        result = result.lcm(try!(unwrap_integer(a)));
+    }
+    Ok(Datum::Number(result))
+}
+
+fn native_expt(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let base = try_unwrap_arg!(args[0] => Number);
+    let exponent = try_unwrap_arg!(args[1] => Number);
+    match base.pow(exponent) {
+        Ok(n) => Ok(Datum::Number(n)),
+        Err(msg) => runtime_error!("{}", msg)
+    }
+}
+
+fn native_exact_integer_sqrt(args: &[Datum]) ->
+    Result<Vec<Datum>, RuntimeError>
+{
+    expect_args!(args == 1);
+    let n = try_unwrap_arg!(args[0] => Number);
+    if !n.is_exact() || !n.is_integer() ||
+        n.sign() == Some(Ordering::Less)
+    {
+        runtime_error!("Expected exact non-negative integer; got {}", n);
+    }
+    let (s, r) = n.exact_integer_sqrt();
+    Ok(vec![Datum::Number(s), Datum::Number(r)])
+}
+
+fn native_floor_divide(args: &[Datum]) -> Result<Vec<Datum>, RuntimeError> {
+    let (q, r) = try!(integer_divide(args, true));
+    Ok(vec![Datum::Number(q), Datum::Number(r)])
+}
+
+fn native_floor_quotient(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    Ok(Datum::Number(try!(integer_divide(args, true)).0))
+}
+
+fn native_floor_remainder(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    Ok(Datum::Number(try!(integer_divide(args, true)).1))
+}
+
+fn native_truncate_divide(args: &[Datum]) -> Result<Vec<Datum>, RuntimeError> {
+    let (q, r) = try!(integer_divide(args, false));
+    Ok(vec![Datum::Number(q), Datum::Number(r)])
+}
+
+fn native_truncate_quotient(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    Ok(Datum::Number(try!(integer_divide(args, false)).0))
+}
+
+fn native_truncate_remainder(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    Ok(Datum::Number(try!(integer_divide(args, false)).1))
+}
+
+// Performs floor or truncate integer division, returning the quotient
+// and remainder.
+fn integer_divide(args: &[Datum], floor: bool) ->
+    Result<(Number, Number), RuntimeError>
+{
+    expect_args!(args == 2);
+    let n = try!(unwrap_integer(&args[0]));
+    let d = try!(unwrap_integer(&args[1]));
+    if d.is_zero() { runtime_error!("Division by zero"); }
+    Ok(n.div_rem(d, floor))
+}
+
+// Unwraps a number that must be an integer, exact or not.
+fn unwrap_integer(arg: &Datum) -> Result<Number, RuntimeError> {
+    let n = try_unwrap_arg!(*arg => Number);
+    if !n.is_integer() { runtime_error!("Expected integer; got {}", n); }
+    Ok(n)
+}
+
+fn native_bitwise_and(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Number::Integer(-1);
+    for a in args {
+        result = result.bitwise_and(try!(unwrap_exact_integer(a)));
+    }
+    Ok(Datum::Number(result))
+}
+
+fn native_bitwise_or(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Number::Integer(0);
+    for a in args {
+        result = result.bitwise_or(try!(unwrap_exact_integer(a)));
+    }
+    Ok(Datum::Number(result))
+}
+
+fn native_bitwise_xor(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Number::Integer(0);
+    for a in args {
+        result = result.bitwise_xor(try!(unwrap_exact_integer(a)));
+    }
+    Ok(Datum::Number(result))
+}
+
+fn native_bitwise_not(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Number(try!(unwrap_exact_integer(&args[0])).bitwise_not()))
+}
+
+fn native_arithmetic_shift(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let n = try!(unwrap_exact_integer(&args[0]));
+    let shift = match try!(unwrap_exact_integer(&args[1])) {
+        Number::Integer(shift) => shift,
+        // Shifting right by this much leaves only the sign, as does the
+        // largest right shift that fits.
+        ref s if s.sign() == Some(Ordering::Less) => i64::min_value(),
+        _ => i64::max_value()
+    };
+    if n.is_zero() { return Ok(Datum::Number(n)); }
+    if shift > number::MAX_SHIFT {
+        runtime_error!("Shift amount too large: {}", args[1]);
+    }
+    Ok(Datum::Number(n.arithmetic_shift(shift)))
+}
+
+fn native_bit_count(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try!(unwrap_exact_integer(&args[0]));
+    Ok(Datum::integer(n.bit_count() as i64))
+}
+
+fn native_integer_length(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try!(unwrap_exact_integer(&args[0]));
+    Ok(Datum::integer(n.integer_length() as i64))
+}
+
+// Usage: (bit-set? index n)
+fn native_bit_set_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let index = try!(unwrap_bit_index(&args[0]));
+    let n = try!(unwrap_exact_integer(&args[1]));
+    Ok(Datum::Boolean(!n.arithmetic_shift(-index).is_even()))
+}
+
+// Usage: (bit-field n start end)
+fn native_bit_field(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 3);
+    let n = try!(unwrap_exact_integer(&args[0]));
+    let start = try!(unwrap_bit_index(&args[1]));
+    let end = try!(unwrap_bit_index(&args[2]));
+    if start > end {
+        runtime_error!("Bit field start {} is after end {}", start, end);
+    }
+    // A non-negative number has no bits set above its length, so the mask
+    // needn't be any wider. A negative one has them all set.
+    let n = n.arithmetic_shift(-start);
+    let width = if n.sign() == Some(Ordering::Less) {
+        end - start
+    } else {
+        ::std::cmp::min(end - start, n.integer_length() as i64 + 1)
+    };
+    if width > number::MAX_SHIFT {
+        runtime_error!("Bit field from {} to {} is too wide", start, end);
+    }
+    let mask = Number::Integer(1).arithmetic_shift(width) - Number::Integer(1);
+    Ok(Datum::Number(n.bitwise_and(mask)))
+}
+
+fn unwrap_exact_integer(arg: &Datum) -> Result<Number, RuntimeError> {
+    let n = try_unwrap_arg!(*arg => Number);
+    if !n.is_exact() || !n.is_integer() {
+        runtime_error!("Expected exact integer; got {}", n);
+    }
+    Ok(n)
+}
+
+fn unwrap_bit_index(arg: &Datum) -> Result<i64, RuntimeError> {
+    let index = try_unwrap_arg!(*arg => i64);
+    if index < 0 { runtime_error!("Bit index must be non-negative"); }
+    Ok(index)
+}
+
+fn native_numerator(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try_unwrap_arg!(args[0] => Number);
+    match n.numerator() {
+        Some(d) => Ok(Datum::Number(d)),
+        None => runtime_error!("Cannot take the numerator of {}", n)
+    }
+}
+
+fn native_denominator(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try_unwrap_arg!(args[0] => Number);
+    match n.denominator() {
+        Some(d) => Ok(Datum::Number(d)),
+        None => runtime_error!("Cannot take the denominator of {}", n)
+    }
+}
+
+fn native_eof_object(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 0);
+    Ok(Datum::Eof)
+}
+
+fn native_exact(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try_unwrap_arg!(args[0] => Number);
+    match n.to_exact() {
+        Some(e) => Ok(Datum::Number(e)),
+        None => runtime_error!("Cannot convert {} to an exact number", n)
+    }
+}
+
+fn native_inexact(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let n = try_unwrap_arg!(args[0] => Number);
+    Ok(Datum::Number(n.to_inexact()))
+}
+
+fn native_ceiling(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Number(try_unwrap_arg!(args[0] => Number).ceiling()))
+}
+
+fn native_floor(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Number(try_unwrap_arg!(args[0] => Number).floor()))
+}
+
+fn native_round(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Number(try_unwrap_arg!(args[0] => Number).round()))
+}
+
+fn native_truncate(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Number(try_unwrap_arg!(args[0] => Number).truncate()))
+}
+
+fn native_multiply(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut product = Number::Integer(1);
+    for a in args {
+        product = product * try_unwrap_arg!(*a => Number);
+    }
+
+    Ok(Datum::Number(product))
+}
+
+fn native_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    Ok(Datum::Boolean(args[0] == args[1]))
+}
+
+fn native_eqv_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    Ok(Datum::Boolean(args[0].eqv(&args[1])))
+}
+
+fn native_hash_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let value = h.borrow().get(&args[1]);
+    Ok(value.unwrap_or(Datum::Boolean(false)))
+}
+
+fn native_hash_set(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 3);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    try!(h.borrow_mut().insert(args[1].clone(), args[2].clone()));
+    Ok(args[2].clone())
+}
+
+fn native_hash_table_contains_p(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    expect_args!(args == 2);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let contains = h.borrow().contains(&args[1]);
+    Ok(Datum::Boolean(contains))
+}
+
+// The optional mutability flag is accepted for compatibility, but all
+// tables are mutable.
+fn native_hash_table_copy(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 2 {
+        runtime_error!("Usage: (hash-table-copy table [mutable?])");
+    }
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let copy = h.borrow().clone();
+    Ok(Datum::hash_table(copy))
+}
+
+fn native_hash_table_count(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let count = h.borrow().len();
+    Ok(Datum::integer(count as i64))
+}
+
+fn native_hash_table_delete(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    h.borrow_mut().remove(&args[1]);
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_hash_table_keys(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let keys = h.borrow().iter().map(|(k, _)| k.clone()).collect();
+    Ok(Datum::list(keys))
+}
+
+fn native_hash_table_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let value = h.borrow().get(&args[1]);
+    match value {
+        Some(v) => Ok(v),
+        None => runtime_error!("Key not found in hash table: {}", args[1])
+    }
+}
+
+fn native_hash_table_ref_default(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    expect_args!(args == 3);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let value = h.borrow().get(&args[1]);
+    Ok(value.unwrap_or(args[2].clone()))
+}
+
+fn native_hash_table_values(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let values = h.borrow().iter().map(|(_, v)| v.clone()).collect();
+    Ok(Datum::list(values))
+}
+
+fn native_hash_table_to_alist(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    expect_args!(args == 1);
+    let h = try_unwrap_arg!(args[0] => HashTable);
+    let entries = h.borrow().iter()
+        .map(|(k, v)| Datum::pair(k.clone(), v.clone())).collect();
+    Ok(Datum::list(entries))
+}
+
+fn native_length(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::integer(try!(args[0].to_vec()).len() as i64))
+}
+
+fn native_list(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let elements: Vec<_> = args.iter().map(|e| e.clone()).collect();
+    Ok(Datum::list(elements))
+}
+
+fn native_iota(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (iota count [start [step]])");
+    }
+    let count = try_unwrap_arg!(args[0] => i64);
+    if count < 0 { runtime_error!("Expected non-negative count"); }
+    let start = if args.len() > 1 {
+        try_unwrap_arg!(args[1] => Number)
+    } else {
+        Number::Integer(0)
+    };
+    let step = if args.len() > 2 {
+        try_unwrap_arg!(args[2] => Number)
+    } else {
+        Number::Integer(1)
+    };
+    let mut elements = Vec::new();
+    for i in 0..count {
+        elements.push(Datum::Number(
+            start.clone() + Number::Integer(i) * step.clone()));
+    }
+    Ok(Datum::list(elements))
+}
+
+fn native_last_pair(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    if args[0].is_cyclic() { runtime_error!("Expected proper list"); }
+    let mut current = args[0].clone();
+    loop {
+        let next = match current {
+            Datum::Pair(ref pair) => pair.borrow().1.clone(),
+            _ => runtime_error!("Expected pair")
+        };
+        match next {
+            Datum::Pair(..) => current = next,
+            _ => return Ok(current)
+        }
+    }
+}
+
+// Copies the pairs making up the list; any improper tail is shared.
+fn native_list_copy(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    if args[0].is_cyclic() { runtime_error!("Expected proper list"); }
+    let mut elements = Vec::new();
+    let mut current = args[0].clone();
+    loop {
+        current = match current {
+            Datum::Pair(ref pair) => {
+                let pair = pair.borrow();
+                elements.push(pair.0.clone());
+                pair.1.clone()
+            },
+            _ => break
+        };
+    }
+    elements.push(current);
+    Ok(Datum::improper_list(elements))
+}
+
+fn native_list_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let k = try_unwrap_arg!(args[1] => i64);
+    match try!(list_tail(&args[0], k)) {
+        Datum::Pair(ref pair) => Ok(pair.borrow().0.clone()),
+        _ => runtime_error!("Index {} out of range for list", k)
+    }
+}
+
+fn native_list_tail(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let k = try_unwrap_arg!(args[1] => i64);
+    list_tail(&args[0], k)
+}
+
+fn list_tail(list: &Datum, k: i64) -> Result<Datum, RuntimeError> {
+    if k < 0 { runtime_error!("Expected non-negative index"); }
+    let mut current = list.clone();
+    for _ in 0..k {
+        current = match current {
+            Datum::Pair(ref pair) => pair.borrow().1.clone(),
+            _ => runtime_error!("Index {} out of range for list", k)
+        };
+    }
+    Ok(current)
+}
+
+fn native_assv(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    find_association(&args[0], &args[1])
+}
+
+// Finds the first pair in the association list whose car is eqv? to the key,
+// or returns #f.
+fn find_association(key: &Datum, alist: &Datum) -> Result<Datum, RuntimeError> {
+    let mut current = alist.clone();
+    loop {
+        current = match current {
+            Datum::Pair(ref pair) => {
+                let (ref entry, ref cdr) = *pair.borrow();
+                match *entry {
+                    Datum::Pair(ref k) => {
+                        if k.borrow().0.eqv(key) { return Ok(entry.clone()); }
+                    },
+                    _ => runtime_error!("Expected association list")
+                }
+                cdr.clone()
+            },
+            _ => return Ok(Datum::Boolean(false))
+        };
+    }
+}
+
+fn native_memv(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    Ok(find_member(&args[0], &args[1]))
+}
+
+// Finds the first sublist whose car is eqv? to the element, or returns #f.
+fn find_member(element: &Datum, list: &Datum) -> Datum {
+    let mut current = list.clone();
+    loop {
+        let next = match current {
+            Datum::Pair(ref pair) => {
+                let (ref car, ref cdr) = *pair.borrow();
+                if car.eqv(element) { break; }
+                cdr.clone()
+            },
+            _ => return Datum::Boolean(false)
+        };
+        current = next;
+    }
+    current
+}
+
+fn native_list_to_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let list = try!(args[0].to_vec());
+    let mut string = String::new();
+    for d in list {
+        let ch = try_unwrap_arg!(d => char);
+        string.push(ch);
+    }
+    Ok(Datum::string(string))
+}
+
+// Makes a hash table comparing keys with the given equivalence predicate, or
+// equal? by default. Only the builtin predicates are supported, since keys
+// must also be hashed consistently with the predicate.
+fn make_hash_table(args: &[Datum], equivalences: &[(Datum, Equivalence)]) ->
+    Result<Datum, RuntimeError>
+{
+    expect_args!(args <= 1);
+    let equivalence = match args.first() {
+        None => Equivalence::Equal,
+        Some(pred) => match equivalences.iter().find(|&&(ref p, _)| p == pred) {
+            Some(&(_, e)) => e,
+            None => runtime_error!(
+                "Unsupported hash table equivalence: {}", pred)
+        }
+    };
+    Ok(Datum::hash_table(HashTable::new(equivalence)))
+}
+
+fn native_null_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    match args[0] {
+        Datum::EmptyList => Ok(Datum::Boolean(true)),
+        _ => Ok(Datum::Boolean(false))
+    }
+}
+
+fn native_string_append(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = String::new();
+    for arg in args {
+        result.push_str(&try_unwrap_arg!(*arg => String));
+    }
+    Ok(Datum::string(result))
+}
+
+fn native_string_contains(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 2);
-    let s1 = try_unwrap_arg!(args[0] => String).clone();
-    let s2 = try_unwrap_arg!(args[1] => String).clone();
+    let s1 = try_unwrap_arg!(args[0] => String);
+    let s2 = try_unwrap_arg!(args[1] => String);
     match s1.find(&s2) {
-        Some(i) => Ok(Datum::Number(i as i64)),
+        // Convert the byte offset to a character index.
+        Some(i) => Ok(Datum::integer(s1[..i].chars().count() as i64)),
         None => Ok(Datum::Boolean(false))
     }
 }
@@ -1000,81 +2204,554 @@ fn native_reverse(args: &[Datum]) -> Result<Datum, RuntimeError> {
 }
 
 fn native_string_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 2);
-    match (&args[0], &args[1]) {
-        (&Datum::String(ref s1), &Datum::String(ref s2)) =>
-            Ok(Datum::Boolean(s1 == s2)),
-        _ => runtime_error!("Usage: (string=? string1 string2)")
+    compare_strings(args, false, |o| o == Ordering::Equal)
+}
+
+fn native_string_less_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_strings(args, false, |o| o == Ordering::Less)
+}
+
+fn native_string_greater_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_strings(args, false, |o| o == Ordering::Greater)
+}
+
+fn native_string_less_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_strings(args, false, |o| o != Ordering::Greater)
+}
+
+fn native_string_greater_equal_p(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    compare_strings(args, false, |o| o != Ordering::Less)
+}
+
+fn native_string_ci_equal_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_strings(args, true, |o| o == Ordering::Equal)
+}
+
+fn native_string_ci_less_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_strings(args, true, |o| o == Ordering::Less)
+}
+
+fn native_string_ci_greater_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    compare_strings(args, true, |o| o == Ordering::Greater)
+}
+
+fn native_string_ci_less_equal_p(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    compare_strings(args, true, |o| o != Ordering::Greater)
+}
+
+fn native_string_ci_greater_equal_p(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    compare_strings(args, true, |o| o != Ordering::Less)
+}
+
+// Checks that the ordering of each adjacent pair of strings satisfies
+// the predicate, optionally comparing case-folded strings.
+fn compare_strings<F: Fn(Ordering) -> bool>(args: &[Datum], fold: bool,
+    pred: F) -> Result<Datum, RuntimeError>
+{
+    expect_args!(args >= 1);
+    let mut strings = Vec::new();
+    for a in args {
+        let s = try_unwrap_arg!(*a => String);
+        strings.push(if fold { string_foldcase(&s) } else { s });
     }
+
+    let res = strings.windows(2).all(|pair| pred(pair[0].cmp(&pair[1])));
+    Ok(Datum::Boolean(res))
+}
+
+fn native_string_downcase(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::string(try_unwrap_arg!(args[0] => String).to_lowercase()))
+}
+
+fn native_string_foldcase(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::string(string_foldcase(&try_unwrap_arg!(args[0] => String))))
+}
+
+fn native_string_upcase(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::string(try_unwrap_arg!(args[0] => String).to_uppercase()))
+}
+
+// Full case folding isn't available, but upcasing first folds the
+// multi-character cases (e.g. "ß" to "ss").
+fn string_foldcase(s: &str) -> String {
+    s.to_uppercase().to_lowercase()
 }
 
 fn native_string_length(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 1);
     let s = try_unwrap_arg!(args[0] => String);
-    Ok(Datum::Number(s.len() as i64))
+    Ok(Datum::integer(s.chars().count() as i64))
 }
 
 fn native_string_prefix_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 2);
-    let s1 = try_unwrap_arg!(args[0] => String).clone();
-    let s2 = try_unwrap_arg!(args[1] => String).clone();
+    let s1 = try_unwrap_arg!(args[0] => String);
+    let s2 = try_unwrap_arg!(args[1] => String);
     Ok(Datum::Boolean(s2.starts_with(&s1)))
 }
 
 fn native_string_split(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 2);
-    let string = try_unwrap_arg!(args[0] => String).clone();
+    let string = try_unwrap_arg!(args[0] => String);
     let ch = try_unwrap_arg!(args[1] => char);
     let splits: Vec<_> = string.split(ch)
         .collect();
     let results = splits.into_iter()
-        .map(|s| Datum::String(s.to_string()))
+        .map(|s| Datum::string(s))
         .collect();
     Ok(Datum::list(results))
 }
 
 fn native_string_to_list(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 1);
-    let s = try_unwrap_arg!(args[0] => String).clone();
-    let list: Vec<_> = s.chars().map(|c| Datum::Character(c)).collect();
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (string->list str [start [end]])");
+    }
+    let s = try_unwrap_arg!(args[0] => String);
+    let (start, end) = try!(get_range(&args[1..], s.chars().count()));
+    let list: Vec<_> = s.chars().skip(start).take(end - start)
+        .map(|c| Datum::Character(c)).collect();
     Ok(Datum::list(list))
 }
 
 fn native_string_to_number(args: &[Datum]) -> Result<Datum, RuntimeError> {
-    expect_args!(args == 1);
-    let s = try_unwrap_arg!(args[0] => String).clone();
-    match s.parse::<i64>() {
-        Ok(n) => Ok(Datum::Number(n)),
-        Err(_) => runtime_error!("Cannot convert {} to a number", &s)
+    if args.len() != 1 && args.len() != 2 {
+        runtime_error!("Usage: (string->number string [radix])");
+    }
+    let s = try_unwrap_arg!(args[0] => String);
+    let radix = if args.len() == 2 { try!(get_radix(&args[1])) } else { 10 };
+    match number::parse_radix(&s, radix) {
+        Some(n) => Ok(Datum::Number(n)),
+        None => Ok(Datum::Boolean(false))
     }
 }
 
 fn native_string_to_symbol(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 1);
-    let s = try_unwrap_arg!(args[0] => String).clone();
-    Ok(Datum::Symbol(s))
+    let s = try_unwrap_arg!(args[0] => String);
+    Ok(Datum::symbol(&s))
+}
+
+fn native_string_to_uninterned_symbol(args: &[Datum]) ->
+    Result<Datum, RuntimeError>
+{
+    expect_args!(args == 1);
+    let s = try_unwrap_arg!(args[0] => String);
+    Ok(Datum::Symbol(Symbol::uninterned(&s)))
+}
+
+// Returns a fresh uninterned symbol, named with an optional string or symbol
+// prefix.
+fn native_gensym(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() > 1 { runtime_error!("Usage: (gensym [prefix])"); }
+    let prefix = match args.first() {
+        None => "g".to_string(),
+        Some(&Datum::String(ref s)) => s.borrow().clone(),
+        Some(&Datum::Symbol(ref s)) => s.name().to_string(),
+        _ => runtime_error!("Usage: (gensym [prefix])")
+    };
+    Ok(Datum::Symbol(Symbol::gensym(&prefix)))
+}
+
+// Returns a fresh uninterned symbol for use as an identifier in generated
+// code. The optional argument is ignored, as in R6RS generate-temporaries.
+fn native_generate_temporary(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() > 1 {
+        runtime_error!("Usage: (generate-temporary [obj])");
+    }
+    Ok(Datum::Symbol(Symbol::gensym("temp")))
 }
 
 fn native_substring(args: &[Datum]) -> Result<Datum, RuntimeError> {
     if args.len() != 2 && args.len() != 3 {
         runtime_error!("Usage: (substring str start [end])");
     }
-    let string = try_unwrap_arg!(args[0] => String);
-    let start = try_unwrap_arg!(args[1] => i64) as usize;
-    let end = if args.len() == 3 { try_unwrap_arg!(args[2] => i64) as usize }
-        else { string.len() };
-    // TODO: Fix i64 <-> usize conversion.
-    if end > string.len() || start > string.len() || start > end {
-        runtime_error!("Cannot index string from {} to {}", start, end);
+    native_string_copy(args)
+}
+
+fn native_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut string = String::new();
+    for arg in args {
+        string.push(try_unwrap_arg!(*arg => char));
+    }
+    Ok(Datum::string(string))
+}
+
+// Wraps the value in an already forced promise, unless it is a promise.
+fn native_make_promise(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    match args[0] {
+        Datum::Promise(_) => Ok(args[0].clone()),
+        _ => Ok(Datum::Promise(Rc::new(Promise::new(
+            PromiseState::Done(args[0].clone())))))
+    }
+}
+
+fn native_make_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() != 1 && args.len() != 2 {
+        runtime_error!("Usage: (make-string k [char])");
+    }
+    let k = try_unwrap_arg!(args[0] => i64);
+    if k < 0 { runtime_error!("String length must be non-negative"); }
+    // Contents are unspecified in the spec if no char is given.
+    let fill = if args.len() == 2 { try_unwrap_arg!(args[1] => char) }
+        else { ' ' };
+    Ok(Datum::string(::std::iter::repeat(fill).take(k as usize)
+        .collect::<String>()))
+}
+
+fn native_string_copy(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (string-copy str [start [end]])");
+    }
+    let s = try_unwrap_arg!(args[0] => String);
+    let (start, end) = try!(get_range(&args[1..], s.chars().count()));
+    let copy: String = s.chars().skip(start).take(end - start).collect();
+    Ok(Datum::string(copy))
+}
+
+fn native_string_copy_to(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 3 || args.len() > 5 {
+        runtime_error!("Usage: (string-copy! to at from [start [end]])");
+    }
+    let to = try!(unwrap_string_cell(&args[0]));
+    let from = try_unwrap_arg!(args[2] => String);
+    let (start, end) = try!(get_range(&args[3..], from.chars().count()));
+    let mut chars: Vec<char> = to.borrow().chars().collect();
+    let at = try_unwrap_arg!(args[1] => i64);
+    if at < 0 || at as u64 + (end - start) as u64 > chars.len() as u64 {
+        runtime_error!("Cannot copy {} characters to index {} for length {}",
+            end - start, at, chars.len());
+    }
+    for (i, c) in from.chars().skip(start).take(end - start).enumerate() {
+        chars[at as usize + i] = c;
+    }
+    *to.borrow_mut() = chars.into_iter().collect();
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_string_fill(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 2 || args.len() > 4 {
+        runtime_error!("Usage: (string-fill! str char [start [end]])");
+    }
+    let s = try!(unwrap_string_cell(&args[0]));
+    let fill = try_unwrap_arg!(args[1] => char);
+    let mut chars: Vec<char> = s.borrow().chars().collect();
+    let (start, end) = try!(get_range(&args[2..], chars.len()));
+    for c in chars[start..end].iter_mut() {
+        *c = fill;
     }
+    *s.borrow_mut() = chars.into_iter().collect();
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_string_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let s = try_unwrap_arg!(args[0] => String);
+    let i = try!(get_index(&args[1], s.chars().count()));
+    Ok(Datum::Character(s.chars().nth(i).unwrap()))
+}
+
+fn native_string_set(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 3);
+    let s = try!(unwrap_string_cell(&args[0]));
+    let c = try_unwrap_arg!(args[2] => char);
+    let mut s = s.borrow_mut();
+    let i = try!(get_index(&args[1], s.chars().count()));
+    let (offset, old) = s.char_indices().nth(i).unwrap();
+    let mut buf = [0; 4];
+    s.replace_range(offset..offset + old.len_utf8(), c.encode_utf8(&mut buf));
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
 
-    Ok(Datum::String((&string[start..end]).to_string()))
+// Returns the shared contents of a string so that it can be mutated.
+fn unwrap_string_cell(arg: &Datum) -> Result<Rc<RefCell<String>>, RuntimeError> {
+    match *arg {
+        Datum::String(ref s) => Ok(s.clone()),
+        _ => runtime_error!("Expected string")
+    }
 }
 
 fn native_symbol_to_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
     expect_args!(args == 1);
-    let s = try_unwrap_arg!(args[0] => Symbol).clone();
-    Ok(Datum::String(s))
+    let s = try_unwrap_arg!(args[0] => Symbol);
+    Ok(Datum::string(s.name()))
+}
+
+fn native_make_vector(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() != 1 && args.len() != 2 {
+        runtime_error!("Usage: (make-vector k [fill])");
+    }
+    let k = try_unwrap_arg!(args[0] => i64);
+    if k < 0 { runtime_error!("Vector length must be non-negative"); }
+    // Contents are unspecified in the spec if no fill is given.
+    let fill = if args.len() == 2 { args[1].clone() } else { Datum::EmptyList };
+    Ok(Datum::vector(vec![fill; k as usize]))
+}
+
+fn native_values(args: &[Datum]) -> Result<Vec<Datum>, RuntimeError> {
+    Ok(args.to_vec())
+}
+
+fn native_vector(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    Ok(Datum::vector(args.to_vec()))
+}
+
+fn native_vector_append(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Vec::new();
+    for arg in args {
+        let v = try_unwrap_arg!(*arg => Vec);
+        result.extend(v.borrow().iter().cloned());
+    }
+    Ok(Datum::vector(result))
+}
+
+fn native_vector_copy(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (vector-copy vec [start [end]])");
+    }
+    let v = try_unwrap_arg!(args[0] => Vec);
+    let v = v.borrow();
+    let (start, end) = try!(get_range(&args[1..], v.len()));
+    Ok(Datum::vector(v[start..end].to_vec()))
+}
+
+fn native_vector_copy_to(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 3 || args.len() > 5 {
+        runtime_error!("Usage: (vector-copy! to at from [start [end]])");
+    }
+    let to = try_unwrap_arg!(args[0] => Vec);
+    let from = try_unwrap_arg!(args[2] => Vec);
+    // Copy out the source elements first in case the vectors overlap.
+    let elements = {
+        let from = from.borrow();
+        let (start, end) = try!(get_range(&args[3..], from.len()));
+        from[start..end].to_vec()
+    };
+    let mut to = to.borrow_mut();
+    let at = try_unwrap_arg!(args[1] => i64);
+    if at < 0 || at as u64 + elements.len() as u64 > to.len() as u64 {
+        runtime_error!("Cannot copy {} elements to index {} for length {}",
+            elements.len(), at, to.len());
+    }
+    for (i, d) in elements.into_iter().enumerate() {
+        to[at as usize + i] = d;
+    }
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_vector_fill(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 2 || args.len() > 4 {
+        runtime_error!("Usage: (vector-fill! vec fill [start [end]])");
+    }
+    let v = try_unwrap_arg!(args[0] => Vec);
+    let mut v = v.borrow_mut();
+    let (start, end) = try!(get_range(&args[2..], v.len()));
+    for d in v[start..end].iter_mut() {
+        *d = args[1].clone();
+    }
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_vector_length(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let v = try_unwrap_arg!(args[0] => Vec);
+    let len = v.borrow().len();
+    Ok(Datum::integer(len as i64))
+}
+
+fn native_vector_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let v = try_unwrap_arg!(args[0] => Vec);
+    let v = v.borrow();
+    let i = try!(get_index(&args[1], v.len()));
+    Ok(v[i].clone())
+}
+
+fn native_vector_set(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 3);
+    let v = try_unwrap_arg!(args[0] => Vec);
+    let mut v = v.borrow_mut();
+    let i = try!(get_index(&args[1], v.len()));
+    v[i] = args[2].clone();
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_vector_to_list(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (vector->list vec [start [end]])");
+    }
+    let v = try_unwrap_arg!(args[0] => Vec);
+    let v = v.borrow();
+    let (start, end) = try!(get_range(&args[1..], v.len()));
+    Ok(Datum::list(v[start..end].to_vec()))
+}
+
+fn native_list_to_vector(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::vector(try!(args[0].to_vec())))
+}
+
+fn native_make_bytevector(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() != 1 && args.len() != 2 {
+        runtime_error!("Usage: (make-bytevector k [byte])");
+    }
+    let k = try_unwrap_arg!(args[0] => i64);
+    if k < 0 { runtime_error!("Bytevector length must be non-negative"); }
+    let fill = if args.len() == 2 { try!(unwrap_byte(&args[1])) } else { 0 };
+    Ok(Datum::bytevector(vec![fill; k as usize]))
+}
+
+fn native_bytevector(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut bytes = Vec::with_capacity(args.len());
+    for arg in args {
+        bytes.push(try!(unwrap_byte(arg)));
+    }
+    Ok(Datum::bytevector(bytes))
+}
+
+fn native_bytevector_append(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    let mut result = Vec::new();
+    for arg in args {
+        let b = try_unwrap_arg!(*arg => Bytevector);
+        result.extend_from_slice(&b.borrow());
+    }
+    Ok(Datum::bytevector(result))
+}
+
+fn native_bytevector_copy(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (bytevector-copy bytevector [start [end]])");
+    }
+    let b = try_unwrap_arg!(args[0] => Bytevector);
+    let b = b.borrow();
+    let (start, end) = try!(get_range(&args[1..], b.len()));
+    Ok(Datum::bytevector(b[start..end].to_vec()))
+}
+
+fn native_bytevector_copy_to(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 3 || args.len() > 5 {
+        runtime_error!("Usage: (bytevector-copy! to at from [start [end]])");
+    }
+    let to = try_unwrap_arg!(args[0] => Bytevector);
+    let from = try_unwrap_arg!(args[2] => Bytevector);
+    // Copy out the source bytes first in case the bytevectors overlap.
+    let bytes = {
+        let from = from.borrow();
+        let (start, end) = try!(get_range(&args[3..], from.len()));
+        from[start..end].to_vec()
+    };
+    let mut to = to.borrow_mut();
+    let at = try_unwrap_arg!(args[1] => i64);
+    if at < 0 || at as u64 + bytes.len() as u64 > to.len() as u64 {
+        runtime_error!("Cannot copy {} bytes to index {} for length {}",
+            bytes.len(), at, to.len());
+    }
+    to[at as usize..at as usize + bytes.len()].copy_from_slice(&bytes);
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_bytevector_length(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let b = try_unwrap_arg!(args[0] => Bytevector);
+    let len = b.borrow().len();
+    Ok(Datum::integer(len as i64))
+}
+
+fn native_bytevector_u8_ref(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 2);
+    let b = try_unwrap_arg!(args[0] => Bytevector);
+    let b = b.borrow();
+    let i = try!(get_index(&args[1], b.len()));
+    Ok(Datum::integer(b[i] as i64))
+}
+
+fn native_bytevector_u8_set(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 3);
+    let b = try_unwrap_arg!(args[0] => Bytevector);
+    let mut b = b.borrow_mut();
+    let i = try!(get_index(&args[1], b.len()));
+    b[i] = try!(unwrap_byte(&args[2]));
+    // Return value is unspecified in the spec.
+    Ok(Datum::Unspecified)
+}
+
+fn native_utf8_to_string(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (utf8->string bytevector [start [end]])");
+    }
+    let b = try_unwrap_arg!(args[0] => Bytevector);
+    let b = b.borrow();
+    let (start, end) = try!(get_range(&args[1..], b.len()));
+    match String::from_utf8(b[start..end].to_vec()) {
+        Ok(s) => Ok(Datum::string(s)),
+        Err(_) => runtime_error!("Bytevector is not valid UTF-8")
+    }
+}
+
+fn native_string_to_utf8(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    if args.len() < 1 || args.len() > 3 {
+        runtime_error!("Usage: (string->utf8 str [start [end]])");
+    }
+    let s = try_unwrap_arg!(args[0] => String);
+    let (start, end) = try!(get_range(&args[1..], s.chars().count()));
+    let substring: String = s.chars().skip(start).take(end - start).collect();
+    Ok(Datum::bytevector(substring.into_bytes()))
+}
+
+fn unwrap_byte(arg: &Datum) -> Result<u8, RuntimeError> {
+    match *arg {
+        Datum::Number(Number::Integer(n)) if n >= 0 && n <= 255 => Ok(n as u8),
+        _ => runtime_error!("Expected byte")
+    }
+}
+
+// Returns the index given by the argument if it is valid for a sequence
+// of the given length.
+fn get_index(arg: &Datum, len: usize) -> Result<usize, RuntimeError> {
+    let i = try_unwrap_arg!(*arg => i64);
+    if i < 0 || i as u64 >= len as u64 {
+        runtime_error!("Index {} out of range for length {}", i, len);
+    }
+    Ok(i as usize)
+}
+
+// Returns the range given by the optional start and end arguments,
+// defaulting to the whole of a sequence of the given length.
+fn get_range(args: &[Datum], len: usize) ->
+    Result<(usize, usize), RuntimeError>
+{
+    let start = if args.len() > 0 { try_unwrap_arg!(args[0] => i64) }
+        else { 0 };
+    let end = if args.len() > 1 { try_unwrap_arg!(args[1] => i64) }
+        else { len as i64 };
+    if start < 0 || end < start || end as u64 > len as u64 {
+        runtime_error!("Range {} to {} out of range for length {}", start, end,
+            len);
+    }
+    Ok((start as usize, end as usize))
+}
+
+// There are no ports to write to, so the written form is returned as a
+// string, which the host can output or store as it sees fit.
+fn native_write_shared(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::string(&args[0].write_shared()))
 }
 
 macro_rules! datum_predicate{
@@ -1091,10 +2768,87 @@ macro_rules! datum_predicate{
 }
 
 datum_predicate!(Datum::Boolean, native_boolean_p);
+datum_predicate!(Datum::Bytevector, native_bytevector_p);
 datum_predicate!(Datum::Character, native_char_p);
+datum_predicate!(Datum::HashTable, native_hash_table_p);
 datum_predicate!(Datum::Number, native_number_p);
 datum_predicate!(Datum::Pair, native_pair_p);
 datum_predicate!(Datum::Procedure, native_procedure_p);
+datum_predicate!(Datum::Promise, native_promise_p);
+datum_predicate!(Datum::Record, native_record_p);
 datum_predicate!(Datum::String, native_string_p);
 datum_predicate!(Datum::Symbol, native_symbol_p);
 datum_predicate!(Datum::Vector, native_vector_p);
+
+fn native_char_alphabetic_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => char).is_alphabetic()))
+}
+
+fn native_char_numeric_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let c = try_unwrap_arg!(args[0] => char);
+    Ok(Datum::Boolean(decimal_digit_value(c).is_some()))
+}
+
+fn native_char_whitespace_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => char).is_whitespace()))
+}
+
+fn native_char_upper_case_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => char).is_uppercase()))
+}
+
+fn native_char_lower_case_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => char).is_lowercase()))
+}
+
+fn native_exact_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => Number).is_exact()))
+}
+
+fn native_inexact_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(!try_unwrap_arg!(args[0] => Number).is_exact()))
+}
+
+fn native_symbol_interned_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => Symbol).is_interned()))
+}
+
+fn native_eof_object_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(args[0] == Datum::Eof))
+}
+
+fn native_zero_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try_unwrap_arg!(args[0] => Number).is_zero()))
+}
+
+fn native_positive_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let sign = try_unwrap_arg!(args[0] => Number).sign();
+    Ok(Datum::Boolean(sign == Some(Ordering::Greater)))
+}
+
+fn native_negative_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    let sign = try_unwrap_arg!(args[0] => Number).sign();
+    Ok(Datum::Boolean(sign == Some(Ordering::Less)))
+}
+
+fn native_odd_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(!try!(unwrap_integer(&args[0])).is_even()))
+}
+
+fn native_even_p(args: &[Datum]) -> Result<Datum, RuntimeError> {
+    expect_args!(args == 1);
+    Ok(Datum::Boolean(try!(unwrap_integer(&args[0])).is_even()))
+}
@@ -87,10 +87,11 @@
 (define apply
   (lambda (proc first . rest)
     (if (null? rest)
-        (let loop ((lst (list proc)) (b first))
-          (if (null? b)
+        ;; Counting the arguments up front rejects improper and cyclic lists.
+        (let loop ((lst (list proc)) (b first) (n (length first)))
+          (if (= n 0)
               (eval lst)
-              (loop (append lst (list (list 'quote (car b)))) (cdr b))))
+              (loop (append lst (list (list 'quote (car b)))) (cdr b) (- n 1))))
         (let loop ((a (list first)) (b rest))
           (if (= 1 (length b))
               (apply proc (append a (car b)))
@@ -109,3 +110,264 @@
         '()
         (cons (apply proc (single-map car lists))
               (apply map proc (single-map cdr lists))))))
+
+(define vector-map
+  (lambda (proc . vectors)
+    (let ((result (make-vector (apply min (single-map vector-length vectors)))))
+      (let loop ((i 0))
+        (if (= i (vector-length result))
+            result
+            (begin
+              (vector-set! result i
+                           (apply proc (single-map (lambda (v) (vector-ref v i))
+                                                   vectors)))
+              (loop (+ i 1))))))))
+
+(define vector-for-each
+  (lambda (proc . vectors)
+    (let ((len (apply min (single-map vector-length vectors))))
+      (let loop ((i 0))
+        (if (< i len)
+            (begin
+              (apply proc (single-map (lambda (v) (vector-ref v i)) vectors))
+              (loop (+ i 1))))))))
+
+(define string-map
+  (lambda (proc . strings)
+    (let ((len (apply min (single-map string-length strings))))
+      (list->string
+        (apply map proc (single-map (lambda (s) (string->list s 0 len))
+                                    strings))))))
+
+(define string-for-each
+  (lambda (proc . strings)
+    (let ((len (apply min (single-map string-length strings))))
+      (let loop ((lists (single-map (lambda (s) (string->list s 0 len))
+                                    strings)))
+        (if (pair? (car lists))
+            (begin
+              (apply proc (single-map car lists))
+              (loop (single-map cdr lists))))))))
+
+(define hash-table-walk
+  (lambda (table proc)
+    (let loop ((entries (hash-table->alist table)))
+      (if (pair? entries)
+          (begin
+            (proc (car (car entries)) (cdr (car entries)))
+            (loop (cdr entries)))))))
+
+;; The optional failure thunk gives the value when the key is missing, and
+;; the optional success procedure is applied to the value when it's found.
+;; Without a failure thunk, a missing key is an error.
+(define hash-table-ref
+  (let ((ref hash-table-ref))
+    (lambda (table key . procs)
+      (if (if (null? procs) #t (hash-table-contains? table key))
+          (let ((value (ref table key)))
+            (if (if (null? procs) #t (null? (cdr procs)))
+                value
+                ((car (cdr procs)) value)))
+          ((car procs))))))
+
+;; The optional thunk gives the value to update when the key is missing.
+(define hash-table-update!
+  (lambda (table key proc . default)
+    (hash-table-set! table key
+                     (proc (if (if (null? default)
+                                   #t
+                                   (hash-table-contains? table key))
+                               (hash-table-ref table key)
+                               ((car default)))))
+    (if #f #f)))
+
+(define hash-table-update!/default
+  (lambda (table key proc default)
+    (hash-table-update! table key proc (lambda () default))))
+
+;; List procedures from SRFI 1. Procedures taking several lists stop at the
+;; end of the shortest, and have a faster path for a single list, which
+;; avoids apply. Results are built up in reverse by loops, so long lists
+;; don't need deep recursion.
+(define every-pair?
+  (lambda (lists)
+    (if (null? lists)
+        #t
+        (if (pair? (car lists))
+            (every-pair? (cdr lists))
+            #f))))
+
+(define for-each
+  (lambda (proc lst . lists)
+    (if (null? lists)
+        (let loop ((lst lst))
+          (if (pair? lst)
+              (begin
+                (proc (car lst))
+                (loop (cdr lst)))))
+        (let loop ((lists (cons lst lists)))
+          (if (every-pair? lists)
+              (begin
+                (apply proc (single-map car lists))
+                (loop (single-map cdr lists))))))))
+
+(define member
+  (lambda (x lst . compare)
+    (let ((same? (if (null? compare) equal? (car compare))))
+      (let loop ((lst lst))
+        (if (pair? lst)
+            (if (same? x (car lst))
+                lst
+                (loop (cdr lst)))
+            #f)))))
+
+(define assoc
+  (lambda (key alist . compare)
+    (let ((same? (if (null? compare) equal? (car compare))))
+      (let loop ((alist alist))
+        (if (pair? alist)
+            (if (same? key (car (car alist)))
+                (car alist)
+                (loop (cdr alist)))
+            #f)))))
+
+(define fold
+  (lambda (kons knil lst . lists)
+    (if (null? lists)
+        (let loop ((acc knil) (lst lst))
+          (if (pair? lst)
+              (loop (kons (car lst) acc) (cdr lst))
+              acc))
+        (let loop ((acc knil) (lists (cons lst lists)))
+          (if (every-pair? lists)
+              (loop (apply kons (append (single-map car lists) (list acc)))
+                    (single-map cdr lists))
+              acc)))))
+
+(define fold-right
+  (lambda (kons knil lst . lists)
+    (if (null? lists)
+        (fold kons knil (reverse lst))
+        (let loop ((rows '()) (lists (cons lst lists)))
+          (if (every-pair? lists)
+              (loop (cons (single-map car lists) rows) (single-map cdr lists))
+              (fold (lambda (row acc) (apply kons (append row (list acc))))
+                    knil
+                    rows))))))
+
+(define reduce
+  (lambda (f ridentity lst)
+    (if (null? lst)
+        ridentity
+        (fold f (car lst) (cdr lst)))))
+
+(define filter
+  (lambda (pred lst)
+    (let loop ((lst lst) (acc '()))
+      (if (pair? lst)
+          (loop (cdr lst) (if (pred (car lst)) (cons (car lst) acc) acc))
+          (reverse acc)))))
+
+(define remove
+  (lambda (pred lst)
+    (filter (lambda (x) (if (pred x) #f #t)) lst)))
+
+(define partition
+  (lambda (pred lst)
+    (let loop ((lst lst) (in '()) (out '()))
+      (if (pair? lst)
+          (if (pred (car lst))
+              (loop (cdr lst) (cons (car lst) in) out)
+              (loop (cdr lst) in (cons (car lst) out)))
+          (values (reverse in) (reverse out))))))
+
+(define delete
+  (lambda (x lst . compare)
+    (let ((same? (if (null? compare) equal? (car compare))))
+      (filter (lambda (y) (if (same? x y) #f #t)) lst))))
+
+(define delete-duplicates
+  (lambda (lst . compare)
+    (let ((same? (if (null? compare) equal? (car compare))))
+      (let loop ((lst lst) (acc '()))
+        (if (pair? lst)
+            (loop (cdr lst)
+                  (if (any (lambda (y) (same? y (car lst))) acc)
+                      acc
+                      (cons (car lst) acc)))
+            (reverse acc))))))
+
+(define any
+  (lambda (pred lst . lists)
+    (if (null? lists)
+        (let loop ((lst lst))
+          (if (pair? lst)
+              (let ((result (pred (car lst))))
+                (if result
+                    result
+                    (loop (cdr lst))))
+              #f))
+        (let loop ((lists (cons lst lists)))
+          (if (every-pair? lists)
+              (let ((result (apply pred (single-map car lists))))
+                (if result
+                    result
+                    (loop (single-map cdr lists))))
+              #f)))))
+
+(define every
+  (lambda (pred lst . lists)
+    (if (null? lists)
+        (let loop ((lst lst) (result #t))
+          (if (pair? lst)
+              (let ((result (pred (car lst))))
+                (if result
+                    (loop (cdr lst) result)
+                    #f))
+              result))
+        (let loop ((lists (cons lst lists)) (result #t))
+          (if (every-pair? lists)
+              (let ((result (apply pred (single-map car lists))))
+                (if result
+                    (loop (single-map cdr lists) result)
+                    #f))
+              result)))))
+
+(define find-tail
+  (lambda (pred lst)
+    (let loop ((lst lst))
+      (if (pair? lst)
+          (if (pred (car lst))
+              lst
+              (loop (cdr lst)))
+          #f))))
+
+(define find
+  (lambda (pred lst)
+    (let ((tail (find-tail pred lst)))
+      (if tail (car tail) #f))))
+
+(define count
+  (lambda (pred lst . lists)
+    (if (null? lists)
+        (let loop ((lst lst) (n 0))
+          (if (pair? lst)
+              (loop (cdr lst) (if (pred (car lst)) (+ n 1) n))
+              n))
+        (let loop ((lists (cons lst lists)) (n 0))
+          (if (every-pair? lists)
+              (loop (single-map cdr lists)
+                    (if (apply pred (single-map car lists)) (+ n 1) n))
+              n)))))
+
+(define append-map
+  (lambda (f lst . lists)
+    (fold append
+          '()
+          (if (null? lists)
+              (fold (lambda (x results) (cons (f x) results)) '() lst)
+              (let loop ((lists (cons lst lists)) (results '()))
+                (if (every-pair? lists)
+                    (loop (single-map cdr lists)
+                          (cons (apply f (single-map car lists)) results))
+                    results))))))
@@ -1,37 +1,70 @@
 use environment::Environment;
+use number::Number;
+use record::{Record, RecordType};
 use error::RuntimeError;
+use hash_table::HashTable;
+use parameter::Parameter;
+use promise::Promise;
+use lexer;
 use std::any::Any;
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::mem;
 use std::rc::Rc;
 use super::mopa;
-use vm::Instruction;
+use symbol::Symbol;
+use vm::{Continuation, Instruction};
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug)]
 pub enum Datum {
-    Symbol(String),
-    String(String),
+    Symbol(Symbol),
+    String(Rc<RefCell<String>>),
     Character(char),
-    Number(i64),
+    Number(Number),
     Boolean(bool),
     Vector(Rc<RefCell<Vec<Datum>>>),
+    Bytevector(Rc<RefCell<Vec<u8>>>),
+    HashTable(Rc<RefCell<HashTable>>),
+    Record(Rc<Record>),
+    RecordType(Rc<RecordType>),
+    Promise(Rc<Promise>),
     Procedure(Procedure),
-    SyntaxRule(Procedure, String),
-    Pair(Box<Datum>, Box<Datum>),
+    SyntaxRule(Procedure, Symbol),
+    // The car and cdr are shared between all copies of the pair so that
+    // mutations are visible through every alias.
+    Pair(Rc<RefCell<(Datum, Datum)>>),
     Ext(Ext),
-    EmptyList
+    EmptyList,
+    // The result of expressions whose value is unspecified in the spec,
+    // such as define and set!.
+    Unspecified,
+    // The end-of-file object.
+    Eof
 }
 
 impl Datum {
     pub fn pair(d1: Datum, d2: Datum) -> Datum {
-        Datum::Pair(Box::new(d1), Box::new(d2))
+        Datum::Pair(Rc::new(RefCell::new((d1, d2))))
     }
     pub fn symbol(s: &str) -> Datum {
-        Datum::Symbol(s.to_string())
+        Datum::Symbol(Symbol::intern(s))
     }
-    pub fn string(s: &str) -> Datum {
-        Datum::String(s.to_string())
+    pub fn string<S: Into<String>>(s: S) -> Datum {
+        Datum::String(Rc::new(RefCell::new(s.into())))
+    }
+    pub fn integer(n: i64) -> Datum {
+        Datum::Number(Number::Integer(n))
+    }
+    pub fn vector(elements: Vec<Datum>) -> Datum {
+        Datum::Vector(Rc::new(RefCell::new(elements)))
+    }
+    pub fn bytevector(bytes: Vec<u8>) -> Datum {
+        Datum::Bytevector(Rc::new(RefCell::new(bytes)))
+    }
+    pub fn hash_table(table: HashTable) -> Datum {
+        Datum::HashTable(Rc::new(RefCell::new(table)))
     }
     pub fn special<T: Fn(Rc<RefCell<Environment>>, &[Datum]) ->
         Result<Vec<Instruction>, RuntimeError> + 'static>(t: T) -> Datum
@@ -42,12 +75,19 @@ impl Datum {
     pub fn native<T: Fn(&[Datum]) ->
         Result<Datum, RuntimeError> + 'static>(t: T) -> Datum
     {
-        Datum::Procedure(Procedure::Native(Rc::new(NativeProcedure(
+        Datum::Procedure(Procedure::Native(Rc::new(NativeProcedure::Single(
+            Box::new(t)))))
+    }
+    // A native procedure that returns any number of values.
+    pub fn native_values<T: Fn(&[Datum]) ->
+        Result<Vec<Datum>, RuntimeError> + 'static>(t: T) -> Datum
+    {
+        Datum::Procedure(Procedure::Native(Rc::new(NativeProcedure::Multiple(
             Box::new(t)))))
     }
     pub fn scheme(
-        arg_names: Vec<String>,
-        rest_name: Option<String>,
+        arg_names: Vec<Symbol>,
+        rest_name: Option<Symbol>,
         body_data: Vec<Datum>,
         saved_env: Rc<RefCell<Environment>>) -> Datum
     {
@@ -58,13 +98,21 @@ impl Datum {
             saved_env: saved_env
         })))
     }
+    // A parameter object, which the host can keep a reference to in order to
+    // read its current value from native procedures.
+    pub fn parameter(parameter: Rc<Parameter>) -> Datum {
+        Datum::Procedure(Procedure::Parameter(parameter))
+    }
+    pub fn continuation(continuation: Rc<Continuation>) -> Datum {
+        Datum::Procedure(Procedure::Continuation(continuation))
+    }
     pub fn ext<E: AnyClone + Eq>(e: E, tag: &str) -> Datum {
         Datum::Ext(Ext::new(e, tag.to_string()))
     }
     pub fn list(elements: Vec<Datum>) -> Datum {
         let mut list = Datum::EmptyList;
         for element in elements.into_iter().rev() {
-            list = Datum::Pair(Box::new(element), Box::new(list));
+            list = Datum::pair(element, list);
         }
         list
     }
@@ -73,7 +121,7 @@ impl Datum {
         let last_loc = elements.len() - 1;
         let mut list = elements[last_loc].clone();
         for element in elements.into_iter().rev().skip(1) {
-            list = Datum::Pair(Box::new(element), Box::new(list));
+            list = Datum::pair(element, list);
         }
         list
     }
@@ -83,42 +131,133 @@ impl Datum {
             &Datum::EmptyList | &Datum::Pair(..) => Datum::EmptyList,
             _ => return self.clone()
         };
-        let mut current = self;
+        let mut current = self.clone();
         loop {
-            match current {
-                &Datum::EmptyList => break,
-                &Datum::Pair(ref a, ref b) => {
-                    reversed = Datum::pair(*a.clone(), reversed);
-                    current = &*b;
+            current = match current {
+                Datum::EmptyList => break,
+                Datum::Pair(ref pair) => {
+                    let pair = pair.borrow();
+                    reversed = Datum::pair(pair.0.clone(), reversed);
+                    pair.1.clone()
                 },
                 a @ _ => {
-                    reversed = Datum::pair(a.clone(), reversed);
+                    reversed = Datum::pair(a, reversed);
                     break;
                 }
-            }
+            };
         }
 
         reversed
     }
     // Returns the vector and a flag indicating whether the datum
-    // was a proper list or not.
+    // was a proper list or not. Cyclic lists are not proper.
     pub fn as_vec(&self) -> (Vec<Datum>, bool) {
         let mut vec: Vec<Datum> = Vec::new();
-        let mut curr = self;
+        let mut curr = self.clone();
+        // Follows the cdrs at half speed; curr only catches up with it if
+        // the list is cyclic.
+        let mut slow = self.clone();
         loop {
-            match curr {
-                &Datum::Pair(ref car, ref cdr) => {
-                    vec.push(*car.clone());
-                    curr = cdr;
+            curr = match curr {
+                Datum::Pair(ref pair) => {
+                    let pair = pair.borrow();
+                    vec.push(pair.0.clone());
+                    pair.1.clone()
                 },
-                &Datum::EmptyList => return (vec, true),
+                Datum::EmptyList => return (vec, true),
                 d @ _ => {
-                    vec.push(d.clone());
+                    vec.push(d);
                     return (vec, false);
                 }
+            };
+            if vec.len() % 2 == 0 {
+                slow = match slow {
+                    Datum::Pair(ref pair) => pair.borrow().1.clone(),
+                    _ => unreachable!()
+                };
             }
+            if curr.same_object(&slow) { return (vec, false); }
+        }
+    }
+    // Whether following the cdrs never reaches the end of the list.
+    pub fn is_cyclic(&self) -> bool {
+        let cdr = |d: &Datum| match *d {
+            Datum::Pair(ref pair) => Some(pair.borrow().1.clone()),
+            _ => None
+        };
+        let mut slow = self.clone();
+        let mut fast = self.clone();
+        loop {
+            fast = match cdr(&fast).and_then(|d| cdr(&d)) {
+                Some(d) => d,
+                None => return false
+            };
+            slow = cdr(&slow).unwrap();
+            if fast.same_object(&slow) { return true; }
+        }
+    }
+    // Takes the bytes out of a bytevector. The bytes are only copied if
+    // the bytevector is still referenced elsewhere.
+    pub fn into_bytes(mut self) -> Option<Vec<u8>> {
+        match self {
+            Datum::Bytevector(ref mut b) => {
+                let b = mem::replace(b, Rc::new(RefCell::new(Vec::new())));
+                Some(match Rc::try_unwrap(b) {
+                    Ok(cell) => cell.into_inner(),
+                    Err(shared) => shared.borrow().clone()
+                })
+            },
+            _ => None
         }
     }
+    // Whether the two data are the same object in memory. Only meaningful
+    // for pairs, strings, vectors and bytevectors; other data are never
+    // considered the same.
+    pub fn same_object(&self, other: &Datum) -> bool {
+        match (self, other) {
+            (&Datum::String(ref a), &Datum::String(ref b)) => Rc::ptr_eq(a, b),
+            (&Datum::Pair(ref a), &Datum::Pair(ref b)) => Rc::ptr_eq(a, b),
+            (&Datum::Vector(ref a), &Datum::Vector(ref b)) => Rc::ptr_eq(a, b),
+            (&Datum::Bytevector(ref a), &Datum::Bytevector(ref b)) =>
+                Rc::ptr_eq(a, b),
+            (&Datum::HashTable(ref a), &Datum::HashTable(ref b)) =>
+                Rc::ptr_eq(a, b),
+            (&Datum::Record(ref a), &Datum::Record(ref b)) => Rc::ptr_eq(a, b),
+            (&Datum::RecordType(ref a), &Datum::RecordType(ref b)) =>
+                Rc::ptr_eq(a, b),
+            (&Datum::Promise(ref a), &Datum::Promise(ref b)) => Rc::ptr_eq(a, b),
+            _ => false
+        }
+    }
+    // Compares the data as with eqv?: mutable objects by identity and
+    // everything else by value.
+    pub fn eqv(&self, other: &Datum) -> bool {
+        match (self, other) {
+            (&Datum::String(..), _) | (&Datum::Pair(..), _) |
+            (&Datum::Vector(..), _) | (&Datum::Bytevector(..), _) |
+            (&Datum::HashTable(..), _) | (&Datum::Record(..), _) |
+            (&Datum::RecordType(..), _) | (&Datum::Promise(..), _) =>
+                self.same_object(other),
+            _ => self == other
+        }
+    }
+    // Identifies the pairs, vectors and records in memory, so that shared
+    // and cyclic structure can be detected.
+    pub fn object_id(&self) -> Option<usize> {
+        match self {
+            &Datum::Pair(ref p) =>
+                Some(&**p as *const RefCell<(Datum, Datum)> as usize),
+            &Datum::Vector(ref v) =>
+                Some(&**v as *const RefCell<Vec<Datum>> as usize),
+            &Datum::Record(ref r) => Some(&**r as *const Record as usize),
+            _ => None
+        }
+    }
+    // Writes the datum using datum labels for all shared structure, as
+    // with write-shared.
+    pub fn write_shared(&self) -> String {
+        format!("{}", Shared(self))
+    }
     // Returns a vector only for the case of a proper list. Errors otherwise.
     pub fn to_vec(&self) -> Result<Vec<Datum>, RuntimeError> {
         let (vec_form, proper) = self.as_vec();
@@ -128,90 +267,445 @@ impl Datum {
 }
 
 impl fmt::Display for Datum {
+    // Writes the datum, using datum labels for any cycles.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            &Datum::Symbol(ref s) => write!(f, "{}", &s),
-            &Datum::String(ref s) => write!(f, "\"{}\"", &s),
-            &Datum::Character(ref c) => write!(f, "#\\{}", c),
-            &Datum::Number(ref n) => write!(f, "{}", n),
-            &Datum::Boolean(ref b) => write!(f, "#{}", if *b {'t'} else {'f'}),
+        Printer::new(self, false).write(f, self)
+    }
+}
+
+// Displays a datum using datum labels for all shared structure.
+struct Shared<'a>(&'a Datum);
+
+impl<'a> fmt::Display for Shared<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Printer::new(self.0, true).write(f, self.0)
+    }
+}
+
+// Writes data with #n= labels for pairs and vectors that are referenced
+// more than once, so that the output can be read back with the same shape.
+struct Printer {
+    // Object ids that need labels, along with the label once written.
+    labels: HashMap<usize, Option<usize>>,
+    next_label: usize
+}
+
+impl Printer {
+    // Labels are always used for cycles. If shared is set, they're also
+    // used for any other pair or vector that appears more than once.
+    fn new(datum: &Datum, shared: bool) -> Printer {
+        let mut printer = Printer {labels: HashMap::new(), next_label: 0};
+        printer.find_labels(datum, shared, &mut HashSet::new(),
+            &mut HashSet::new());
+        printer
+    }
+
+    fn find_labels(&mut self, datum: &Datum, shared: bool,
+        seen: &mut HashSet<usize>, active: &mut HashSet<usize>)
+    {
+        // Walk down the cdrs iteratively so long lists don't recurse.
+        let mut chain = Vec::new();
+        let mut current = datum.clone();
+        loop {
+            let id = match current.object_id() {
+                Some(id) => id,
+                None => break
+            };
+            if seen.contains(&id) {
+                // Objects still being walked are part of a cycle.
+                if shared || active.contains(&id) {
+                    self.labels.insert(id, None);
+                }
+                break;
+            }
+            seen.insert(id);
+            active.insert(id);
+            chain.push(id);
+            current = match current {
+                Datum::Pair(ref pair) => {
+                    let pair = pair.borrow();
+                    self.find_labels(&pair.0, shared, seen, active);
+                    pair.1.clone()
+                },
+                Datum::Vector(ref v) => {
+                    for d in v.borrow().iter() {
+                        self.find_labels(d, shared, seen, active);
+                    }
+                    break;
+                },
+                Datum::Record(ref r) => {
+                    for d in r.fields.borrow().iter() {
+                        self.find_labels(d, shared, seen, active);
+                    }
+                    break;
+                },
+                _ => break
+            };
+        }
+        for id in chain {
+            active.remove(&id);
+        }
+    }
+
+    // Writes the label for the object if it needs one. Returns true if
+    // the object has already been written so only a reference was needed.
+    fn write_label(&mut self, f: &mut fmt::Formatter, id: usize) ->
+        Result<bool, fmt::Error>
+    {
+        match self.labels.get_mut(&id) {
+            Some(&mut Some(n)) => {
+                try!(write!(f, "#{}#", n));
+                Ok(true)
+            },
+            Some(label) => {
+                *label = Some(self.next_label);
+                try!(write!(f, "#{}=", self.next_label));
+                self.next_label += 1;
+                Ok(false)
+            },
+            None => Ok(false)
+        }
+    }
+
+    fn is_labeled(&self, datum: &Datum) -> bool {
+        match datum.object_id() {
+            Some(id) => self.labels.contains_key(&id),
+            None => false
+        }
+    }
+
+    fn write(&mut self, f: &mut fmt::Formatter, datum: &Datum) -> fmt::Result {
+        if let Some(id) = datum.object_id() {
+            if try!(self.write_label(f, id)) { return Ok(()); }
+        }
+        match datum {
             &Datum::Vector(ref v) => {
-                use std::ops::Deref;
                 try!(write!(f, "#("));
-                let borrowed = v.borrow();
-                let vec = borrowed.deref();
-                for (index, d) in vec.iter().enumerate() {
-                    try!(d.fmt(f));
-                    if index < vec.len() - 1 {
+                for (index, d) in v.borrow().iter().enumerate() {
+                    if index > 0 {
                         try!(write!(f, " "));
                     }
+                    try!(self.write(f, d));
                 }
                 write!(f, ")")
             },
-            &Datum::Procedure(_) => write!(f, "#<procedure>"),
-            &Datum::SyntaxRule(_, ref name) =>
-                write!(f, "#<syntax-rule:{}>", name),
-            &Datum::Pair(ref car, ref cdr) => {
-                let car_str = format!("{}", car);
-                let cdr_str = format!("{}", cdr);
-
+            &Datum::Pair(ref pair) => {
                 try!(write!(f, "("));
-                if cdr_str.chars().next() == Some('(') {
-                    // If the cdr is a list, leave out the . and parentheses.
-                    try!(write!(f, "{}", &car_str));
-                    match **cdr {
-                        Datum::EmptyList => (),
-                        _ => try!(write!(f, " "))
-                    }
-                    try!(write!(f, "{}", &cdr_str[1..cdr_str.len() - 1]));
-                } else {
-                    try!(write!(f, "{}", &car_str));
-                    try!(write!(f, " . "));
-                    try!(write!(f, "{}", &cdr_str));
+                let (car, mut rest) = pair.borrow().clone();
+                try!(self.write(f, &car));
+                loop {
+                    // Continue the list unless the cdr needs a label.
+                    rest = match rest {
+                        Datum::EmptyList => break,
+                        Datum::Pair(ref pair) if !self.is_labeled(&rest) => {
+                            let (car, cdr) = pair.borrow().clone();
+                            try!(write!(f, " "));
+                            try!(self.write(f, &car));
+                            cdr
+                        },
+                        ref other => {
+                            try!(write!(f, " . "));
+                            try!(self.write(f, other));
+                            break;
+                        }
+                    };
                 }
                 write!(f, ")")
             },
-            &Datum::Ext(ref e) => write!(f, "#<ext:{}>", &e.tag),
-            &Datum::EmptyList => write!(f, "()"),
+            &Datum::Record(ref r) => {
+                try!(write!(f, "#<record {}", r.record_type.short_name()));
+                let fields = r.fields.borrow();
+                for (name, d) in r.record_type.fields.iter().zip(fields.iter()) {
+                    try!(write!(f, " {}: ", name));
+                    try!(self.write(f, d));
+                }
+                write!(f, ">")
+            },
+            _ => write_atom(f, datum)
         }
     }
 }
 
+fn write_atom(f: &mut fmt::Formatter, datum: &Datum) -> fmt::Result {
+    match datum {
+        &Datum::Symbol(ref s) => {
+            if lexer::is_plain_identifier(s) {
+                write!(f, "{}", &s)
+            } else {
+                write_escaped(f, s, '|')
+            }
+        },
+        &Datum::String(ref s) => write_escaped(f, &s.borrow(), '"'),
+        &Datum::Character(ref c) => {
+            match get_name_for_char(*c) {
+                Some(name) => write!(f, "#\\{}", name),
+                None if c.is_control() =>
+                    write!(f, "#\\x{:x}", *c as u32),
+                None => write!(f, "#\\{}", c)
+            }
+        },
+        &Datum::Number(ref n) => write!(f, "{}", n),
+        &Datum::Boolean(ref b) => write!(f, "#{}", if *b {'t'} else {'f'}),
+        &Datum::Bytevector(ref b) => {
+            try!(write!(f, "#u8("));
+            for (index, byte) in b.borrow().iter().enumerate() {
+                if index > 0 {
+                    try!(write!(f, " "));
+                }
+                try!(write!(f, "{}", byte));
+            }
+            write!(f, ")")
+        },
+        &Datum::HashTable(_) => write!(f, "#<hash-table>"),
+        &Datum::RecordType(ref t) =>
+            write!(f, "#<record-type {}>", t.short_name()),
+        &Datum::Promise(_) => write!(f, "#<promise>"),
+        &Datum::Procedure(_) => write!(f, "#<procedure>"),
+        &Datum::SyntaxRule(_, ref name) =>
+            write!(f, "#<syntax-rule:{}>", name),
+        &Datum::Ext(ref e) => write!(f, "#<ext:{}>", &e.tag),
+        &Datum::EmptyList => write!(f, "()"),
+        &Datum::Unspecified => write!(f, "#<unspecified>"),
+        &Datum::Eof => write!(f, "#<eof>"),
+        &Datum::Vector(..) | &Datum::Pair(..) | &Datum::Record(..) =>
+            unreachable!("compound data are written by the printer")
+    }
+}
+
+// Writes a string or |symbol| with the given delimiters, escaping any
+// characters that can't be read back literally.
+fn write_escaped(f: &mut fmt::Formatter, s: &str, delimiter: char) ->
+    fmt::Result
+{
+    try!(write!(f, "{}", delimiter));
+    for c in s.chars() {
+        try!(match c {
+            '\\' => write!(f, "\\\\"),
+            '\u{7}' => write!(f, "\\a"),
+            '\u{8}' => write!(f, "\\b"),
+            '\t' => write!(f, "\\t"),
+            '\n' => write!(f, "\\n"),
+            '\r' => write!(f, "\\r"),
+            c if c == delimiter => write!(f, "\\{}", c),
+            c if c.is_control() => write!(f, "\\x{:x};", c as u32),
+            c => write!(f, "{}", c)
+        });
+    }
+    write!(f, "{}", delimiter)
+}
+
+// Returns the name the reader uses for the character, if any.
+fn get_name_for_char(c: char) -> Option<&'static str> {
+    let name = match c {
+        '\u{7}' => "alarm",
+        '\u{8}' => "backspace",
+        '\u{7f}' => "delete",
+        '\u{1b}' => "escape",
+        '\n' => "newline",
+        '\0' => "null",
+        '\r' => "return",
+        ' ' => "space",
+        '\t' => "tab",
+        _ => return None
+    };
+    Some(name)
+}
+
+// Hashing looks at no more than this many pairs, vectors and records, so
+// that cyclic data hash in finite time. Data that are equal? unfold into the same
+// structure, so they still hash the same.
+const HASH_LIMIT: usize = 1000;
+
 impl Hash for Datum {
     fn hash<H>(&self, state: &mut H) where H: Hasher {
-        match self {
-            &Datum::Symbol(ref v) => v.hash(state),
-            &Datum::String(ref v) => v.hash(state),
-            &Datum::Character(ref v) => v.hash(state),
-            &Datum::Number(ref v) => v.hash(state),
-            &Datum::Boolean(ref v) => v.hash(state),
-            &Datum::Vector(ref v) => v.borrow().hash(state),
-            &Datum::Procedure(_) => unimplemented!(),
-            &Datum::SyntaxRule(..) => unimplemented!(),
-            &Datum::Pair(ref car, ref cdr) => {
-                car.hash(state);
-                cdr.hash(state);
+        hash_bounded(self, state, &mut HASH_LIMIT.clone());
+    }
+}
+
+fn hash_bounded<H>(datum: &Datum, state: &mut H, budget: &mut usize)
+    where H: Hasher
+{
+    match datum {
+        &Datum::Symbol(ref v) => v.hash(state),
+        &Datum::String(ref v) => v.borrow().hash(state),
+        &Datum::Character(ref v) => v.hash(state),
+        &Datum::Number(ref v) => v.hash(state),
+        &Datum::Boolean(ref v) => v.hash(state),
+        &Datum::Vector(ref v) => {
+            if *budget == 0 { return; }
+            *budget -= 1;
+            let v = v.borrow();
+            v.len().hash(state);
+            for d in v.iter() {
+                hash_bounded(d, state, budget);
+            }
+        },
+        &Datum::Bytevector(ref b) => b.borrow().hash(state),
+        &Datum::HashTable(ref h) =>
+            (&**h as *const RefCell<HashTable> as usize).hash(state),
+        &Datum::Record(ref r) => {
+            if *budget == 0 { return; }
+            *budget -= 1;
+            (&*r.record_type as *const RecordType as usize).hash(state);
+            for d in r.fields.borrow().iter() {
+                hash_bounded(d, state, budget);
+            }
+        },
+        &Datum::RecordType(ref t) =>
+            (&**t as *const RecordType as usize).hash(state),
+        &Datum::Promise(ref p) =>
+            (&**p as *const Promise as usize).hash(state),
+        &Datum::Procedure(ref p) => p.hash(state),
+        &Datum::SyntaxRule(ref p, _) => p.hash(state),
+        &Datum::Pair(..) => {
+            // Walk down the cdrs iteratively so long lists don't recurse.
+            let mut current = datum.clone();
+            loop {
+                current = match current {
+                    Datum::Pair(_) if *budget == 0 => break,
+                    Datum::Pair(ref pair) => {
+                        *budget -= 1;
+                        let pair = pair.borrow();
+                        hash_bounded(&pair.0, state, budget);
+                        pair.1.clone()
+                    },
+                    ref other => {
+                        hash_bounded(other, state, budget);
+                        break;
+                    }
+                };
+            }
+        },
+        // Ext data can't be hashed in general, so all of them hash the
+        // same and are told apart by their comparer.
+        &Datum::Ext(..) => 0xE7.hash(state),
+        &Datum::EmptyList => 0xDEAD.hash(state), // arbitrary
+        &Datum::Unspecified => 0xBEEF.hash(state),
+        &Datum::Eof => 0xE0F.hash(state)
+    }
+}
+
+// Compares the data structurally, as with equal?.
+impl PartialEq for Datum {
+    fn eq(&self, other: &Datum) -> bool {
+        equal(self, other, &mut HashSet::new())
+    }
+}
+
+// Pairs of objects already being compared are recorded in visited and taken
+// to be equal when they're met again, so that cyclic structures compare in
+// finite time.
+fn equal(a: &Datum, b: &Datum, visited: &mut HashSet<(usize, usize)>) -> bool {
+    // Walk down the cdrs iteratively so long lists don't recurse.
+    let mut a = a.clone();
+    let mut b = b.clone();
+    loop {
+        if a.same_object(&b) { return true; }
+        if let (Some(x), Some(y)) = (a.object_id(), b.object_id()) {
+            if !visited.insert((x, y)) { return true; }
+        }
+        let (next_a, next_b) = match (&a, &b) {
+            (&Datum::Pair(ref pair1), &Datum::Pair(ref pair2)) => {
+                let (pair1, pair2) = (pair1.borrow(), pair2.borrow());
+                if !equal(&pair1.0, &pair2.0, visited) { return false; }
+                (pair1.1.clone(), pair2.1.clone())
             },
-            // TODO: Implement this.
-            &Datum::Ext(..) => unimplemented!(),
-            &Datum::EmptyList => 0xDEAD.hash(state) // arbitrary
+            (&Datum::Pair(..), _) => return false,
+            _ => break
+        };
+        a = next_a;
+        b = next_b;
+    }
+    match (&a, &b) {
+        (&Datum::Symbol(ref x), &Datum::Symbol(ref y)) => x == y,
+        (&Datum::String(ref x), &Datum::String(ref y)) => x == y,
+        (&Datum::Character(ref x), &Datum::Character(ref y)) => x == y,
+        (&Datum::Number(ref x), &Datum::Number(ref y)) => x == y,
+        (&Datum::Boolean(ref x), &Datum::Boolean(ref y)) => x == y,
+        (&Datum::Vector(ref x), &Datum::Vector(ref y)) => {
+            let (x, y) = (x.borrow(), y.borrow());
+            x.len() == y.len() &&
+                x.iter().zip(y.iter()).all(|(d, e)| equal(d, e, visited))
+        },
+        (&Datum::Bytevector(ref x), &Datum::Bytevector(ref y)) => x == y,
+        (&Datum::HashTable(ref x), &Datum::HashTable(ref y)) => x == y,
+        (&Datum::Record(ref x), &Datum::Record(ref y)) => {
+            let (fields1, fields2) = (x.fields.borrow(), y.fields.borrow());
+            x.is_instance(&y.record_type) &&
+                fields1.iter().zip(fields2.iter())
+                    .all(|(d, e)| equal(d, e, visited))
+        },
+        (&Datum::RecordType(ref x), &Datum::RecordType(ref y)) =>
+            Rc::ptr_eq(x, y),
+        (&Datum::Promise(ref x), &Datum::Promise(ref y)) => Rc::ptr_eq(x, y),
+        (&Datum::Procedure(ref x), &Datum::Procedure(ref y)) => x == y,
+        (&Datum::SyntaxRule(ref x, ref m), &Datum::SyntaxRule(ref y, ref n))
+            => x == y && m == n,
+        (&Datum::Ext(ref x), &Datum::Ext(ref y)) => x == y,
+        (&Datum::EmptyList, &Datum::EmptyList) |
+        (&Datum::Unspecified, &Datum::Unspecified) |
+        (&Datum::Eof, &Datum::Eof) => true,
+        _ => false
+    }
+}
+
+impl Eq for Datum {}
+
+// Drops the pairs of a list one at a time, rather than recursively down the
+// cdrs, so that long lists don't overflow the stack. The same goes for the
+// values of forced promises, which make up the tails of streams. Only tails
+// that aren't referenced elsewhere are unlinked.
+impl Drop for Datum {
+    fn drop(&mut self) {
+        let mut rest = match take_tail(self) {
+            Some(tail) => tail,
+            None => return
+        };
+        while let Some(next) = take_tail(&rest) {
+            rest = next;
         }
     }
 }
 
+fn take_tail(datum: &Datum) -> Option<Datum> {
+    match *datum {
+        Datum::Pair(ref pair) => Some(take_unshared(pair)),
+        Datum::Promise(ref p) if Rc::strong_count(p) == 1 => p.take_value(),
+        _ => None
+    }
+}
+
+fn take_unshared(pair: &Rc<RefCell<(Datum, Datum)>>) -> Datum {
+    if Rc::strong_count(pair) == 1 {
+        mem::replace(&mut pair.borrow_mut().1, Datum::EmptyList)
+    } else {
+        Datum::EmptyList
+    }
+}
+
+impl From<Vec<u8>> for Datum {
+    fn from(bytes: Vec<u8>) -> Datum {
+        Datum::bytevector(bytes)
+    }
+}
+
 pub enum Procedure {
     SpecialForm(Rc<SpecialForm>),
     Native(Rc<NativeProcedure>),
-    Scheme(Rc<SchemeProcedure>)
+    Scheme(Rc<SchemeProcedure>),
+    Parameter(Rc<Parameter>),
+    Continuation(Rc<Continuation>)
 }
 
 pub struct SpecialForm(Box<Fn(Rc<RefCell<Environment>>, &[Datum]) ->
     Result<Vec<Instruction>, RuntimeError>>);
-pub struct NativeProcedure(Box<Fn(&[Datum]) ->
-    Result<Datum, RuntimeError>>);
+pub enum NativeProcedure {
+    Single(Box<Fn(&[Datum]) -> Result<Datum, RuntimeError>>),
+    Multiple(Box<Fn(&[Datum]) -> Result<Vec<Datum>, RuntimeError>>)
+}
 pub struct SchemeProcedure {
-    pub arg_names: Vec<String>,
-    pub rest_name: Option<String>,
+    pub arg_names: Vec<Symbol>,
+    pub rest_name: Option<Symbol>,
     pub body_data: Vec<Datum>,
     pub saved_env: Rc<RefCell<Environment>>
 }
@@ -237,10 +731,23 @@ impl fmt::Debug for NativeProcedure {
 }
 
 impl NativeProcedure {
-    pub fn call(&self, args: &[Datum]) ->
-        Result<Datum, RuntimeError>
+    // Calls the procedure and pushes its results onto the stack, returning
+    // the number of values pushed.
+    pub fn call(&self, args: &[Datum], stack: &mut Vec<Datum>) ->
+        Result<usize, RuntimeError>
     {
-        self.0(args)
+        match self {
+            &NativeProcedure::Single(ref f) => {
+                stack.push(try!(f(args)));
+                Ok(1)
+            },
+            &NativeProcedure::Multiple(ref f) => {
+                let mut values = try!(f(args));
+                let n = values.len();
+                stack.append(&mut values);
+                Ok(n)
+            }
+        }
     }
 }
 
@@ -256,7 +763,9 @@ impl fmt::Debug for Procedure {
             // TODO: Implement this properly.
             Procedure::Native(ref n) => n.fmt(f),
             Procedure::Scheme(ref s) => s.fmt(f),
-            Procedure::SpecialForm(ref s) => s.fmt(f)
+            Procedure::SpecialForm(ref s) => s.fmt(f),
+            Procedure::Parameter(ref p) => p.fmt(f),
+            Procedure::Continuation(_) => write!(f, "#<continuation>")
         }
     }
 }
@@ -266,20 +775,44 @@ impl Clone for Procedure {
         match self {
             &Procedure::SpecialForm(ref r) => Procedure::SpecialForm(r.clone()),
             &Procedure::Native(ref r) => Procedure::Native(r.clone()),
-            &Procedure::Scheme(ref r) => Procedure::Scheme(r.clone())
+            &Procedure::Scheme(ref r) => Procedure::Scheme(r.clone()),
+            &Procedure::Parameter(ref r) => Procedure::Parameter(r.clone()),
+            &Procedure::Continuation(ref r) =>
+                Procedure::Continuation(r.clone())
+        }
+    }
+}
+
+impl Procedure {
+    // Identifies the procedure in memory; procedures are only equal to
+    // themselves.
+    fn address(&self) -> usize {
+        match self {
+            &Procedure::SpecialForm(ref s) =>
+                &**s as *const SpecialForm as usize,
+            &Procedure::Native(ref n) => &**n as *const NativeProcedure as usize,
+            &Procedure::Scheme(ref s) => &**s as *const SchemeProcedure as usize,
+            &Procedure::Parameter(ref p) => &**p as *const Parameter as usize,
+            &Procedure::Continuation(ref c) =>
+                &**c as *const Continuation as usize
         }
     }
 }
 
 impl PartialEq for Procedure {
-    fn eq(&self, _: &Procedure) -> bool {
-        // TODO: Fix this.
-        true
+    fn eq(&self, other: &Procedure) -> bool {
+        self.address() == other.address()
     }
 }
 
 impl Eq for Procedure {}
 
+impl Hash for Procedure {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.address().hash(state)
+    }
+}
+
 // Used to store trait objects that are cloneable and can be
 // downcast later on.
 pub trait AnyClone: mopa::Any {
@@ -342,10 +875,54 @@ impl Clone for Ext {
 
 #[test]
 fn test_reverse() {
-    assert_eq!(list!(Datum::Number(1), Datum::Number(2), Datum::Number(3))
+    assert_eq!(list!(Datum::integer(1), Datum::integer(2), Datum::integer(3))
         .reverse(),
-        list!(Datum::Number(3), Datum::Number(2), Datum::Number(1)));
+        list!(Datum::integer(3), Datum::integer(2), Datum::integer(1)));
     assert_eq!(Datum::EmptyList.reverse(), Datum::EmptyList);
-    assert_eq!(Datum::Number(1).reverse(), Datum::Number(1));
-    assert_eq!(list!(Datum::Number(1)).reverse(), list!(Datum::Number(1)));
+    assert_eq!(Datum::integer(1).reverse(), Datum::integer(1));
+    assert_eq!(list!(Datum::integer(1)).reverse(), list!(Datum::integer(1)));
+}
+
+#[test]
+fn test_long_list() {
+    let list = Datum::list((0..1000000).map(Datum::integer).collect());
+    assert_eq!(list.clone(), list.reverse().reverse());
+    let mut hasher = ::std::collections::hash_map::DefaultHasher::new();
+    list.hash(&mut hasher);
+    // Dropping the last reference frees the pairs without recursing.
+    drop(list);
+}
+
+#[test]
+fn test_into_bytes() {
+    let bytes = vec![1, 2, 3];
+    let ptr = bytes.as_ptr();
+    let datum = Datum::from(bytes);
+    let bytes = datum.into_bytes().unwrap();
+    // The buffer is moved rather than copied when not shared.
+    assert_eq!(bytes.as_ptr(), ptr);
+    assert_eq!(bytes, vec![1, 2, 3]);
+
+    let datum = Datum::bytevector(vec![4, 5]);
+    let alias = datum.clone();
+    assert_eq!(datum.into_bytes(), Some(vec![4, 5]));
+    assert_eq!(alias.into_bytes(), Some(vec![4, 5]));
+    assert_eq!(Datum::integer(1).into_bytes(), None);
+}
+
+#[test]
+fn test_write_shared() {
+    let shared = list!(Datum::symbol("x"));
+    let list = list!(shared.clone(), shared.clone());
+    assert_eq!(format!("{}", list), "((x) (x))");
+    assert_eq!(list.write_shared(), "(#0=(x) #0#)");
+
+    let cycle = list!(Datum::symbol("a"), Datum::symbol("b"));
+    if let Datum::Pair(ref pair) = cycle {
+        if let Datum::Pair(ref cdr) = pair.borrow().1 {
+            cdr.borrow_mut().1 = cycle.clone();
+        }
+    }
+    assert_eq!(format!("{}", cycle), "#0=(a b . #0#)");
+    assert_eq!(cycle.write_shared(), "#0=(a b . #0#)");
 }
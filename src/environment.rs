@@ -3,11 +3,13 @@ use error::RuntimeError;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
+use symbol::Symbol;
 
+// Names can be given as interned symbols or as strings, which are interned.
 #[derive(Debug)]
 pub struct Environment {
     parent: Option<Rc<RefCell<Environment>>>,
-    bindings: HashMap<String, Datum>
+    bindings: HashMap<Symbol, Datum>
 }
 
 impl Environment {
@@ -17,19 +19,21 @@ impl Environment {
     pub fn with_parent(parent: Rc<RefCell<Environment>>) -> Self {
         Environment {parent: Some(parent), bindings: HashMap::new()}
     }
-    pub fn define(&mut self, name: &str, datum: Datum) {
-        self.bindings.insert(name.to_string(), datum);
+    pub fn define<S: Into<Symbol>>(&mut self, name: S, datum: Datum) {
+        self.bindings.insert(name.into(), datum);
     }
-    pub fn define_fn<F: Fn(&[Datum]) -> Result<Datum, RuntimeError> + 'static>(
-        &mut self, name: &str, func: F)
+    pub fn define_fn<S: Into<Symbol>,
+        F: Fn(&[Datum]) -> Result<Datum, RuntimeError> + 'static>(
+        &mut self, name: S, func: F)
     {
-        self.bindings.insert(name.to_string(), Datum::native(func));
+        self.bindings.insert(name.into(), Datum::native(func));
     }
-    pub fn set(&mut self, name: &str, datum: Datum) ->
+    pub fn set<S: Into<Symbol>>(&mut self, name: S, datum: Datum) ->
         Result<(), RuntimeError>
     {
-        if self.bindings.contains_key(name) {
-            self.bindings.insert(name.to_string(), datum);
+        let name = name.into();
+        if self.bindings.contains_key(&name) {
+            self.bindings.insert(name, datum);
             Ok(())
         } else {
             match self.parent {
@@ -39,21 +43,24 @@ impl Environment {
             }
         }
     }
-    pub fn get(&self, name: &str) -> Option<Datum> {
+    pub fn get<S: Into<Symbol>>(&self, name: S) -> Option<Datum> {
+        self.get_symbol(&name.into())
+    }
+    fn get_symbol(&self, name: &Symbol) -> Option<Datum> {
         match self.bindings.get(name) {
             Some(d) => Some(d.clone()),
             None => {
                 match self.parent {
-                    Some(ref p) => p.borrow().get(name),
+                    Some(ref p) => p.borrow().get_symbol(name),
                     None => None
                 }
             }
         }
     }
-    pub fn contains(&self, name: &str) -> bool {
-        self.bindings.contains_key(name)
+    pub fn contains<S: Into<Symbol>>(&self, name: S) -> bool {
+        self.bindings.contains_key(&name.into())
     }
-    pub fn iter(&self) -> ::std::collections::hash_map::Iter<String, Datum> {
+    pub fn iter(&self) -> ::std::collections::hash_map::Iter<Symbol, Datum> {
         self.bindings.iter()
     }
 }
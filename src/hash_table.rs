@@ -0,0 +1,145 @@
+use datum::Datum;
+use error::RuntimeError;
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// The predicate used to compare keys in a hash table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Equivalence {
+    Eq,
+    Eqv,
+    Equal,
+    String
+}
+
+// A mutable hash table. Keys are hashed and compared according to the
+// table's equivalence, so that for example two equal strings are distinct
+// keys in an eqv? table but the same key in an equal? table.
+#[derive(Clone, Debug)]
+pub struct HashTable {
+    equivalence: Equivalence,
+    entries: HashMap<Key, Datum>
+}
+
+// Hash tables are only equal to themselves, as with eqv?.
+impl PartialEq for HashTable {
+    fn eq(&self, other: &HashTable) -> bool {
+        ::std::ptr::eq(self, other)
+    }
+}
+
+impl Eq for HashTable {}
+
+impl HashTable {
+    pub fn new(equivalence: Equivalence) -> Self {
+        HashTable {equivalence: equivalence, entries: HashMap::new()}
+    }
+    pub fn equivalence(&self) -> Equivalence {
+        self.equivalence
+    }
+    pub fn get(&self, key: &Datum) -> Option<Datum> {
+        self.entries.get(&self.key(key)).cloned()
+    }
+    pub fn contains(&self, key: &Datum) -> bool {
+        self.entries.contains_key(&self.key(key))
+    }
+    pub fn insert(&mut self, key: Datum, value: Datum) ->
+        Result<(), RuntimeError>
+    {
+        if self.equivalence == Equivalence::String {
+            match key {
+                Datum::String(_) => (),
+                _ => runtime_error!(
+                    "Expected string key for string=? hash table; got {}",
+                    key)
+            }
+        }
+        let key = self.key(&key);
+        self.entries.insert(key, value);
+        Ok(())
+    }
+    pub fn remove(&mut self, key: &Datum) -> Option<Datum> {
+        let key = self.key(key);
+        self.entries.remove(&key)
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn iter(&self) -> Iter {
+        Iter(self.entries.iter())
+    }
+    fn key(&self, datum: &Datum) -> Key {
+        Key(datum.clone(), self.equivalence)
+    }
+}
+
+pub struct Iter<'a>(hash_map::Iter<'a, Key, Datum>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a Datum, &'a Datum);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (&k.0, v))
+    }
+}
+
+// A key along with the equivalence it is hashed and compared with.
+#[derive(Clone, Debug)]
+struct Key(Datum, Equivalence);
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        match self.1 {
+            Equivalence::Eq | Equivalence::Eqv => self.0.eqv(&other.0),
+            Equivalence::Equal | Equivalence::String => self.0 == other.0
+        }
+    }
+}
+
+impl Eq for Key {}
+
+impl Hash for Key {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        match self.1 {
+            Equivalence::Eq | Equivalence::Eqv => match address(&self.0) {
+                Some(address) => address.hash(state),
+                None => self.0.hash(state)
+            },
+            Equivalence::Equal | Equivalence::String => self.0.hash(state)
+        }
+    }
+}
+
+// Gives the address of data that eqv? compares by identity.
+fn address(datum: &Datum) -> Option<usize> {
+    match datum {
+        &Datum::String(ref s) => Some(&**s as *const _ as *const u8 as usize),
+        &Datum::Bytevector(ref b) =>
+            Some(&**b as *const _ as *const u8 as usize),
+        &Datum::HashTable(ref h) =>
+            Some(&**h as *const _ as *const u8 as usize),
+        _ => datum.object_id()
+    }
+}
+
+#[test]
+fn test_equivalence() {
+    let mut eqv = HashTable::new(Equivalence::Eqv);
+    let mut equal = HashTable::new(Equivalence::Equal);
+    let key = Datum::string("a");
+    eqv.insert(key.clone(), Datum::integer(1)).unwrap();
+    equal.insert(key.clone(), Datum::integer(1)).unwrap();
+
+    assert_eq!(eqv.get(&key), Some(Datum::integer(1)));
+    assert_eq!(eqv.get(&Datum::string("a")), None);
+    assert_eq!(equal.get(&Datum::string("a")), Some(Datum::integer(1)));
+
+    // Extension data are compared with their own equality.
+    equal.insert(Datum::ext(5, "five"), Datum::integer(5)).unwrap();
+    assert_eq!(equal.get(&Datum::ext(5, "five")), Some(Datum::integer(5)));
+    assert_eq!(equal.get(&Datum::ext(6, "six")), None);
+
+    let mut strings = HashTable::new(Equivalence::String);
+    assert!(strings.insert(Datum::symbol("a"), Datum::integer(1)).is_err());
+    assert_eq!(strings.len(), 0);
+}
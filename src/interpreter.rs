@@ -10,7 +10,8 @@ use std::rc::Rc;
 use vm::VirtualMachine;
 
 pub struct Interpreter {
-    root: Rc<RefCell<Environment>>
+    root: Rc<RefCell<Environment>>,
+    fold_case: bool
 }
 
 impl Interpreter {
@@ -20,7 +21,10 @@ impl Interpreter {
         for (name, datum) in builtin::get_builtins() {
             root.define(name, datum);
         }
-        let interp = Interpreter {root: Rc::new(RefCell::new(root))};
+        let interp = Interpreter {
+            root: Rc::new(RefCell::new(root)),
+            fold_case: false
+        };
         interp.evaluate(include_str!("core.scm"))
             .expect("Error in the core scheme library");
         interp
@@ -37,15 +41,40 @@ impl Interpreter {
         let mut deref = env.deref_mut();
         func(deref);
     }
+    // Reads identifiers case-insensitively, as was the default before
+    // R7RS. Source can still override this with #!no-fold-case.
+    pub fn set_fold_case(&mut self, fold_case: bool) {
+        self.fold_case = fold_case;
+    }
     pub fn run_repl(&self) {
         repl::run("> ", |s| {
-            let res = try!(self.evaluate(&s));
-            Ok(format!("{}", res))
+            // Each value is printed on its own line.
+            let lines: Vec<_> = try!(self.evaluate_values(&s)).iter()
+                .filter(|d| **d != Datum::Unspecified)
+                .map(|d| format!("{}", d))
+                .collect();
+            if lines.is_empty() {
+                Ok(None)
+            } else {
+                Ok(Some(lines.join("\n")))
+            }
         });
     }
+    // Evaluates to the value of the last datum, which must not return
+    // multiple values. Returning no values gives an unspecified value.
     pub fn evaluate(&self, s: &str) -> Result<Datum, String> {
+        let mut values = try!(self.evaluate_values(s));
+        match values.len() {
+            0 => Ok(Datum::Unspecified),
+            1 => Ok(values.remove(0)),
+            n => Err(format!("Expected 1 value; got {}", n))
+        }
+    }
+    // Evaluates to all of the values of the last datum.
+    pub fn evaluate_values(&self, s: &str) -> Result<Vec<Datum>, String> {
         // Lex.
         let mut lexer = Lexer::new(s.chars());
+        lexer.set_fold_case(self.fold_case);
         let tokens = match lexer.lex_all() {
             Ok(t) => t,
             Err(e) => return Err(e.msg)
@@ -61,7 +90,7 @@ impl Interpreter {
         if data.len() == 0 {return Err("".to_string());}
 
         // Evaluate.
-        let mut res = Datum::EmptyList;
+        let mut res = Vec::new();
         for datum in data {
             res = match self.evaluate_datum(&datum) {
                 Ok(d) => d,
@@ -72,7 +101,7 @@ impl Interpreter {
         Ok(res)
     }
     pub fn evaluate_datum(&self, datum: &Datum) ->
-        Result<Datum, (RuntimeError, String)>
+        Result<Vec<Datum>, (RuntimeError, String)>
     {
         let mut vm = VirtualMachine::new();
         vm.run(self.root.clone(), datum)
@@ -1,3 +1,4 @@
+use number::{self, Number};
 use std::fmt;
 use std::iter::Peekable;
 
@@ -5,13 +6,17 @@ use std::iter::Peekable;
 pub enum Token {
     OpenParen,
     OpenVectorParen,
+    OpenBytevectorParen,
     CloseParen,
     Identifier(String),
     String(String),
     Character(char),
-    Number(i64),
+    Number(Number),
     Boolean(bool),
     Dot,
+    DatumComment,
+    DatumLabel(u64),
+    DatumReference(u64),
     Quote,
     Quasiquote,
     Unquote,
@@ -41,12 +46,23 @@ macro_rules! syntax_error {
 pub struct Lexer<I: Iterator<Item=char>> {
     input: Peekable<I>,
     line: u64,
-    column: u64
+    column: u64,
+    // Whether identifiers and character names are folded to lower case.
+    // Toggled within the input by #!fold-case and #!no-fold-case.
+    fold_case: bool,
+    // The currently open parens and brackets with their line and column,
+    // so that closing ones can be checked against their own kind.
+    open_brackets: Vec<(char, u64, u64)>
 }
 
 impl<I: Iterator<Item=char>> Lexer<I> {
     pub fn new(input: I) -> Self {
-        Lexer {input: input.peekable(), line: 1, column: 1}
+        Lexer {input: input.peekable(), line: 1, column: 1, fold_case: false,
+            open_brackets: Vec::new()}
+    }
+
+    pub fn set_fold_case(&mut self, fold_case: bool) {
+        self.fold_case = fold_case;
     }
 
     pub fn lex_all(&mut self) -> Result<Vec<Token>, SyntaxError> {
@@ -68,8 +84,14 @@ impl<I: Iterator<Item=char>> Lexer<I> {
         };
 
         match ch {
-            '(' => Ok(Some(Token::OpenParen)),
-            ')' => Ok(Some(Token::CloseParen)),
+            '(' | '[' => {
+                self.open_bracket(ch);
+                Ok(Some(Token::OpenParen))
+            },
+            ')' | ']' => {
+                try!(self.close_bracket(ch));
+                Ok(Some(Token::CloseParen))
+            },
             '\'' => Ok(Some(Token::Quote)),
             '`' => Ok(Some(Token::Quasiquote)),
             ',' => {
@@ -81,24 +103,46 @@ impl<I: Iterator<Item=char>> Lexer<I> {
                     _ => Ok(Some(Token::Unquote))
                 }
             },
-            // Skip these characters; they're reserved for future use.
-            '[' | ']' | '{' | '}' | '|' => self.lex_token(),
+            // These characters are reserved for future use.
+            '{' | '}' => syntax_error!(self, "Reserved character {}", ch),
+            '|' => Ok(Some(try!(self.lex_pipe_identifier()))),
             // Deal with the various hash lexes.
             '#' => {
                 match self.next_char() {
                     Some('t') => Ok(Some(Token::Boolean(true))),
                     Some('f') => Ok(Some(Token::Boolean(false))),
-                    Some('(') => Ok(Some(Token::OpenVectorParen)),
+                    Some('(') => {
+                        self.open_bracket('(');
+                        Ok(Some(Token::OpenVectorParen))
+                    },
+                    Some(';') => Ok(Some(Token::DatumComment)),
+                    Some('|') => {
+                        try!(self.skip_block_comment());
+                        self.lex_token()
+                    },
+                    Some('u') => {
+                        match (self.next_char(), self.next_char()) {
+                            (Some('8'), Some('(')) => {
+                                self.open_bracket('(');
+                                Ok(Some(Token::OpenBytevectorParen))
+                            },
+                            _ => syntax_error!(self, "Expected #u8(")
+                        }
+                    },
                     Some('\\') => {
                         match self.next_char() {
                             Some(c) => {
                                 if is_a_z(c) {
                                     // An alphabetic char could indicate
-                                    // a spelled out character name.
+                                    // a spelled out character name or a
+                                    // hex scalar value.
                                     let mut char_name = String::new();
                                     char_name.push(c);
-                                    char_name.push_str(
-                                        &self.read_while(|c| is_a_z(c)));
+                                    char_name.push_str(&self.read_while(
+                                        |c| c.is_ascii_alphanumeric()));
+                                    if self.fold_case && char_name.len() > 1 {
+                                        char_name = char_name.to_lowercase();
+                                    }
                                     match get_char_for_name(&char_name) {
                                         Some(c) => Ok(Some(Token::Character(c))),
                                         None => syntax_error!(self,
@@ -113,8 +157,48 @@ impl<I: Iterator<Item=char>> Lexer<I> {
                                 "Expected character after #\\")
                         }
                     },
-                    Some('e') | Some('i') | Some('b') | Some('o') | Some('d') |
-                        Some('x') => unimplemented!(),
+                    // Datum labels (#n=) and references to them (#n#).
+                    Some(c) if c.is_digit(10) => {
+                        let mut digits = String::new();
+                        digits.push(c);
+                        digits.push_str(&self.read_while(|c| c.is_digit(10)));
+                        let n = match digits.parse::<u64>() {
+                            Ok(n) => n,
+                            Err(_) => syntax_error!(self,
+                                "Datum label too large: {}", &digits)
+                        };
+                        match self.next_char() {
+                            Some('=') => Ok(Some(Token::DatumLabel(n))),
+                            Some('#') => Ok(Some(Token::DatumReference(n))),
+                            _ => syntax_error!(self,
+                                "Expected = or # after datum label #{}", n)
+                        }
+                    },
+                    // Directives, which produce no token.
+                    Some('!') => {
+                        let directive = self.read_while(
+                            |c| is_identifier_char(c));
+                        match &directive[..] {
+                            "fold-case" => self.fold_case = true,
+                            "no-fold-case" => self.fold_case = false,
+                            _ => syntax_error!(self,
+                                "Unknown directive: #!{}", &directive)
+                        }
+                        self.lex_token()
+                    },
+                    // Radix and exactness prefixes for numbers.
+                    Some(c) if "eibodxEIBODX".contains(c) => {
+                        let mut s = String::new();
+                        s.push('#');
+                        s.push(c);
+                        s.push_str(&self.read_while(
+                            |c| is_identifier_char(c) || c == '#'));
+                        match number::parse(&s) {
+                            Some(n) => Ok(Some(Token::Number(n))),
+                            None => syntax_error!(self,
+                                "Number not in valid form: {}", &s)
+                        }
+                    },
                     None => syntax_error!(self, "Expected character after #"),
                     _ => syntax_error!(self, "Unexpected character after #")
                 }
@@ -125,7 +209,7 @@ impl<I: Iterator<Item=char>> Lexer<I> {
                 self.lex_token()
             },
             // Handle dots and ellipses.
-            '.' => {
+            c @ '.' => {
                 match self.input.peek() {
                     Some(&'.') => {
                         // Check for ellipses ...
@@ -137,6 +221,8 @@ impl<I: Iterator<Item=char>> Lexer<I> {
                                 "Identifiers cannot begin with .")
                         }
                     },
+                    Some(&d) if d.is_digit(10) =>
+                        Ok(Some(try!(self.lex_number(c)))),
                     Some(&d) if is_identifier_char(d) =>
                         syntax_error!(self, "Identifiers cannot begin with ."),
                     _ => Ok(Some(Token::Dot))
@@ -149,8 +235,20 @@ impl<I: Iterator<Item=char>> Lexer<I> {
             // +/- can indicate the beginning of a number or be an identifier.
             c if c == '+' || c == '-' => {
                 match self.input.peek() {
-                    Some(&d) if d.is_digit(10) =>
+                    Some(&d) if d.is_digit(10) || d == '.' =>
                         Ok(Some(try!(self.lex_number(c)))),
+                    // Could be one of the special inexact values +inf.0,
+                    // -inf.0, +nan.0, or -nan.0.
+                    Some(&d) if d == 'i' || d == 'n' => {
+                        let mut s = String::new();
+                        s.push(c);
+                        s.push_str(&self.read_while(|c| is_identifier_char(c)));
+                        match number::parse(&s) {
+                            Some(n) => Ok(Some(Token::Number(n))),
+                            None => syntax_error!(self,
+                                "Identifiers cannot begin with +/-")
+                        }
+                    },
                     Some(&d) if is_identifier_char(d) => syntax_error!(self,
                         "Identifiers cannot begin with +/-"),
                     _ => {
@@ -163,7 +261,26 @@ impl<I: Iterator<Item=char>> Lexer<I> {
             // Lex identifier. Note that identifiers cannot start with a digit,
             // a plus sign, a minus sign, or a dot. Those are lexed higher up.
             c if is_identifier_char(c) => Ok(Some(self.lex_identifier(c))),
-            _ => unimplemented!()
+            c => syntax_error!(self, "Unexpected character {}", c)
+        }
+    }
+
+    // Records an opening paren or bracket that was just read.
+    fn open_bracket(&mut self, open: char) {
+        let position = (open, self.line, self.column - 1);
+        self.open_brackets.push(position);
+    }
+
+    // Checks that a closing paren or bracket that was just read matches
+    // the most recent opening one. Unbalanced closings are left for the
+    // parser to report.
+    fn close_bracket(&mut self, close: char) -> Result<(), SyntaxError> {
+        let expected = if close == ')' { '(' } else { '[' };
+        match self.open_brackets.pop() {
+            Some((open, line, column)) if open != expected => syntax_error!(
+                self, "Mismatched {} closing {} opened at line {}, column {}",
+                close, open, line, column),
+            _ => Ok(())
         }
     }
 
@@ -198,53 +315,137 @@ impl<I: Iterator<Item=char>> Lexer<I> {
     }
 
     fn lex_string(&mut self) -> Result<Token, SyntaxError> {
+        Ok(Token::String(try!(self.read_delimited('"', "string"))))
+    }
+
+    fn lex_pipe_identifier(&mut self) -> Result<Token, SyntaxError> {
+        Ok(Token::Identifier(try!(self.read_delimited('|', "symbol"))))
+    }
+
+    // Reads up to the closing delimiter of a string or |symbol|, handling
+    // escape sequences along the way.
+    fn read_delimited(&mut self, delimiter: char, kind: &str) ->
+        Result<String, SyntaxError>
+    {
         let mut s = String::new();
         loop {
-            let part = self.read_while(|c| c != '\"' && c != '\n' && c != '\\');
+            // Raw newlines are allowed within strings.
+            let part = self.read_while(|c| c != delimiter && c != '\\');
             s.push_str(&part);
             match self.next_char() {
-                Some('\"') => return Ok(Token::String(s)),
-                Some('\n') => syntax_error!(self, "Unterminated string"),
+                Some(c) if c == delimiter => return Ok(s),
                 Some('\\') => {
                     // Handle escape characters.
-                    // TODO: Handle the rest.
                     match self.next_char() {
-                        Some('n') => s.push('\n'),
+                        Some('a') => s.push('\u{7}'),
+                        Some('b') => s.push('\u{8}'),
                         Some('t') => s.push('\t'),
+                        Some('n') => s.push('\n'),
+                        Some('r') => s.push('\r'),
                         Some('"') => s.push('"'),
-                        Some(_) => unimplemented!(),
+                        Some('\\') => s.push('\\'),
+                        Some('|') => s.push('|'),
+                        Some('x') => {
+                            let hex = self.read_while(|c| c.is_digit(16));
+                            let c = match u32::from_str_radix(&hex, 16) {
+                                Ok(n) => char::from_u32(n),
+                                Err(_) => None
+                            };
+                            match (c, self.next_char()) {
+                                (Some(c), Some(';')) => s.push(c),
+                                _ => syntax_error!(self,
+                                    "Invalid hex escape in {}: \\x{}", kind,
+                                    &hex)
+                            }
+                        },
+                        // A line continuation skips the line ending and
+                        // the whitespace surrounding it.
+                        Some(c) if c == '\n' || is_intraline_whitespace(c) => {
+                            if c != '\n' {
+                                self.read_while(is_intraline_whitespace);
+                                if self.next_char() != Some('\n') {
+                                    syntax_error!(self,
+                                        "Expected line ending after \\");
+                                }
+                            }
+                            self.read_while(is_intraline_whitespace);
+                        },
+                        Some(c) => syntax_error!(self,
+                            "Unknown escape sequence in {}: \\{}", kind, c),
                         None => syntax_error!(self,
                             "Unterminated escape sequence")
                     }
                 },
-                _ => syntax_error!(self, "Unterminated string")
+                _ => syntax_error!(self, "Unterminated {}", kind)
             }
         }
     }
 
+    // Skips a #| |# comment, which may be nested. The opening #| has
+    // already been read.
+    fn skip_block_comment(&mut self) -> Result<(), SyntaxError> {
+        let mut depth = 1;
+        while depth > 0 {
+            match (self.next_char(), self.input.peek().cloned()) {
+                (Some('|'), Some('#')) => {
+                    self.next_char();
+                    depth -= 1;
+                },
+                (Some('#'), Some('|')) => {
+                    self.next_char();
+                    depth += 1;
+                },
+                (Some(_), _) => (),
+                (None, _) => syntax_error!(self, "Unterminated block comment")
+            }
+        }
+        Ok(())
+    }
+
     fn lex_identifier(&mut self, first: char) -> Token {
         let mut identifier = String::new();
         identifier.push(first);
         identifier.push_str(&self.read_while(|c| is_identifier_char(c)));
-        Token::Identifier(identifier.to_lowercase())
+        if self.fold_case {
+            identifier = identifier.to_lowercase();
+        }
+        Token::Identifier(identifier)
     }
 
     fn lex_number(&mut self, first: char) -> Result<Token, SyntaxError> {
-        // TODO: Handle decimal points and arbitrary precision numbers.
         let mut s = String::new();
-        // Leave out any plus sign so the number can be properly parsed.
-        if first != '+' { s.push(first); }
-        s.push_str(&self.read_while(|c| c.is_digit(10)));
-        match i64::from_str_radix(&s, 10) {
-            Ok(n) => Ok(Token::Number(n)),
-            Err(e) => syntax_error!(self, "Integer not in valid form: {}",
-                e.to_string())
+        s.push(first);
+        s.push_str(&self.read_while(|c| is_identifier_char(c)));
+        match number::parse(&s) {
+            Some(n) => Ok(Token::Number(n)),
+            None => syntax_error!(self, "Number not in valid form: {}", &s)
         }
     }
 }
 
+// Carriage returns are included so that CRLF line endings are handled.
+fn is_intraline_whitespace(ch: char) -> bool {
+    ch == ' ' || ch == '\t' || ch == '\r'
+}
+
+// Whether the symbol can be written without |pipes| and read back as
+// the same symbol.
+pub fn is_plain_identifier(s: &str) -> bool {
+    if s == "+" || s == "-" || s == "..." { return true; }
+    match s.chars().next() {
+        Some(c) if c.is_digit(10) || c == '+' || c == '-' || c == '.' =>
+            return false,
+        None => return false,
+        _ => ()
+    }
+    s.chars().all(is_identifier_char)
+}
+
+// Letters outside ASCII are allowed too, so that symbols like λ can be
+// read and written without bars.
 fn is_identifier_char(ch: char) -> bool {
-    is_a_z(ch) || is_extended_alphabetic(ch) || ch.is_digit(10)
+    is_a_z(ch) || is_extended_alphabetic(ch) || ch.is_digit(10) ||
+        ch.is_alphabetic()
 }
 
 fn is_a_z(ch: char) -> bool {
@@ -277,14 +478,24 @@ fn get_char_for_name(name: &str) -> Option<char> {
 
     if name.len() == 1 {
         Some(name.chars().next().unwrap())
+    } else if name.starts_with('x') {
+        // Hex scalar value, e.g. #\x41.
+        match u32::from_str_radix(&name[1..], 16) {
+            Ok(n) => char::from_u32(n),
+            Err(_) => None
+        }
     } else {
-        // TODO: Support more.
         let ch = match name {
-            "space" => ' ',
+            "alarm" => '\u{7}',
+            "backspace" => '\u{8}',
+            "delete" => '\u{7f}',
+            "escape" => '\u{1b}',
             "newline" => '\n',
             "nl" => '\n',
-            "tab" => '\t',
+            "null" => '\0',
             "return" => '\r',
+            "space" => ' ',
+            "tab" => '\t',
             _ => return None
         };
         Some(ch)
@@ -298,9 +509,10 @@ fn lex_math() {
     let tokens = lexer.lex_all();
     assert!(tokens.ok().unwrap() == vec![
         Token::OpenParen, Token::Identifier(String::from("+")),
-        Token::Number(2), Token::OpenParen,
+        Token::Number(Number::Integer(2)), Token::OpenParen,
         Token::Identifier(String::from("*")),
-        Token::Number(100), Token::Number(5), Token::CloseParen,
+        Token::Number(Number::Integer(100)), Token::Number(Number::Integer(5)),
+        Token::CloseParen,
         Token::CloseParen
     ]);
 }
@@ -312,9 +524,9 @@ fn lex_numbers() {
     let tokens = lexer.lex_all();
     assert!(tokens.ok().unwrap() == vec![
         Token::OpenParen,
-        Token::Number(1234567890),
-        Token::Number(1234567890),
-        Token::Number(-1234567890),
+        Token::Number(Number::Integer(1234567890)),
+        Token::Number(Number::Integer(1234567890)),
+        Token::Number(Number::Integer(-1234567890)),
         Token::CloseParen
     ]);
 }
@@ -327,11 +539,51 @@ fn lex_identifiers() {
     assert!(tokens.ok().unwrap() == vec![
         Token::OpenParen,
         Token::Identifier(String::from("abcdefghijklmnopqrstuvwxyz!@$%^&*.~")),
-        Token::Identifier(String::from("abcdefg")),
+        Token::Identifier(String::from("AbCdEfG")),
         Token::CloseParen
     ]);
 }
 
+#[test]
+fn lex_fold_case() {
+    let s = String::from("(AbC #\\SPACE)");
+    let mut lexer = Lexer::new(s.chars());
+    lexer.set_fold_case(true);
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen, Token::Identifier(String::from("abc")),
+        Token::Character(' '), Token::CloseParen
+    ]);
+}
+
+#[test]
+fn lex_fold_case_directives() {
+    let s = String::from("Abc #!fold-case Abc #!no-fold-case Abc");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::Identifier(String::from("Abc")),
+        Token::Identifier(String::from("abc")),
+        Token::Identifier(String::from("Abc"))
+    ]);
+
+    let s = String::from("#!unknown");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_braces() {
+    let s = String::from("(a {b})");
+    let mut lexer = Lexer::new(s.chars());
+    let error = lexer.lex_all().err().unwrap();
+    assert!(error.line == 1 && error.column == 5);
+
+    let s = String::from("}");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
 #[test]
 fn lex_plus_minus_dot_identifiers() {
     let s = String::from("(+ - . a+-.b +");
@@ -383,8 +635,8 @@ fn lex_dot_and_ellipses() {
         Token::OpenParen,
         Token::Dot,
         Token::Identifier(String::from("...")),
-        Token::Number(1),
-        Token::Number(2),
+        Token::Number(Number::Integer(1)),
+        Token::Number(Number::Integer(2)),
         Token::Dot
     ]);
 }
@@ -446,11 +698,173 @@ fn lex_unterminated_string() {
 
 #[test]
 fn lex_string_split_across_lines() {
-    let s = String::from("(\"string split\nacross lines)");
+    let s = String::from("(\"string split\nacross lines\")");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen,
+        Token::String(String::from("string split\nacross lines")),
+        Token::CloseParen
+    ]);
+
+    let s = String::from("(\"unterminated\nstring)");
     let mut lexer = Lexer::new(s.chars());
     let tokens = lexer.lex_all();
     let error = tokens.err().unwrap();
-    assert!(error.line == 2 && error.column == 1);
+    assert!(error.line == 2 && error.column == 8);
+}
+
+#[test]
+fn lex_string_escapes() {
+    let s = String::from(
+        "\"\\\\ \\a\\b\\t\\n\\r \\x41;\\x3bb; \\| C:\\\\dir\"");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::String(String::from("\\ \u{7}\u{8}\t\n\r Aλ | C:\\dir"))
+    ]);
+
+    let s = String::from("\"\\q\"");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+
+    let s = String::from("\"\\x41\"");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_string_line_continuation() {
+    let s = String::from("\"one \\\n    two \\  \r\n\tthree\"");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::String(String::from("one two three"))
+    ]);
+
+    let s = String::from("\"one \\ two\"");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_brackets() {
+    let s = String::from("(let ([x 1]) x)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen, Token::Identifier(String::from("let")),
+        Token::OpenParen, Token::OpenParen,
+        Token::Identifier(String::from("x")), Token::Number(Number::Integer(1)),
+        Token::CloseParen, Token::CloseParen,
+        Token::Identifier(String::from("x")), Token::CloseParen
+    ]);
+}
+
+#[test]
+fn lex_mismatched_brackets() {
+    let s = String::from("(let ([x 1)]\n  x)");
+    let mut lexer = Lexer::new(s.chars());
+    let error = lexer.lex_all().err().unwrap();
+    assert_eq!(error.msg, "Mismatched ) closing [ opened at line 1, column 7");
+    assert!(error.line == 1 && error.column == 12);
+
+    let s = String::from("#(1\n 2]");
+    let mut lexer = Lexer::new(s.chars());
+    let error = lexer.lex_all().err().unwrap();
+    assert!(error.line == 2 && error.column == 4);
+}
+
+#[test]
+fn lex_datum_labels() {
+    let s = String::from("#0=(a . #0#) #12=");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::DatumLabel(0), Token::OpenParen,
+        Token::Identifier(String::from("a")), Token::Dot,
+        Token::DatumReference(0), Token::CloseParen, Token::DatumLabel(12)
+    ]);
+
+    let s = String::from("#1x");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_block_comments() {
+    let s = String::from("(1 #| a #| nested |# comment |# 2)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen, Token::Number(Number::Integer(1)),
+        Token::Number(Number::Integer(2)), Token::CloseParen
+    ]);
+
+    let s = String::from("#| a #| b |#");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_datum_comment() {
+    let s = String::from("#;(a) b");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::DatumComment, Token::OpenParen,
+        Token::Identifier(String::from("a")), Token::CloseParen,
+        Token::Identifier(String::from("b"))
+    ]);
+}
+
+#[test]
+fn lex_pipe_identifiers() {
+    let s = String::from("(|hello world| || |a\\|b| |\\x41;|)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen, Token::Identifier(String::from("hello world")),
+        Token::Identifier(String::from("")),
+        Token::Identifier(String::from("a|b")),
+        Token::Identifier(String::from("A")), Token::CloseParen
+    ]);
+
+    let s = String::from("|unterminated");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_is_plain_identifier() {
+    assert!(is_plain_identifier("abc"));
+    assert!(is_plain_identifier("+"));
+    assert!(is_plain_identifier("..."));
+    assert!(is_plain_identifier("list->vector"));
+    assert!(!is_plain_identifier(""));
+    assert!(!is_plain_identifier("hello world"));
+    assert!(!is_plain_identifier("1abc"));
+    assert!(!is_plain_identifier("+a"));
+    assert!(!is_plain_identifier("."));
+    assert!(!is_plain_identifier("a|b"));
+    assert!(is_plain_identifier("λ"));
+    assert!(!is_plain_identifier("a»b"));
+}
+
+#[test]
+fn lex_unexpected_characters() {
+    let s = String::from("('λ 'Straße)");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().ok().unwrap() == vec![
+        Token::OpenParen, Token::Quote, Token::Identifier(String::from("λ")),
+        Token::Quote, Token::Identifier(String::from("Straße")),
+        Token::CloseParen
+    ]);
+
+    for s in &["\\", "'a»b", "(a \\ b)", "»"] {
+        let mut lexer = Lexer::new(s.chars());
+        assert!(lexer.lex_all().is_err());
+    }
 }
 
 #[test]
@@ -460,8 +874,8 @@ fn lex_quotes() {
     let tokens = lexer.lex_all();
     assert!(tokens.ok().unwrap() == vec![
         Token::OpenParen, Token::Quote, Token::OpenParen, Token::CloseParen,
-        Token::Quasiquote, Token::OpenParen, Token::Unquote, Token::Number(1),
-        Token::UnquoteList, Token::OpenParen, Token::Number(2),
+        Token::Quasiquote, Token::OpenParen, Token::Unquote,
+        Token::Number(Number::Integer(1)), Token::UnquoteList, Token::OpenParen, Token::Number(Number::Integer(2)),
         Token::CloseParen, Token::CloseParen, Token::CloseParen
     ]);
 }
@@ -479,6 +893,24 @@ fn lex_characters() {
     ]);
 }
 
+#[test]
+fn lex_character_names() {
+    let s = String::from(
+        "(#\\alarm #\\backspace #\\delete #\\escape #\\null #\\x41 #\\x3bb #\\x)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen, Token::Character('\u{7}'), Token::Character('\u{8}'),
+        Token::Character('\u{7f}'), Token::Character('\u{1b}'),
+        Token::Character('\0'), Token::Character('A'), Token::Character('λ'),
+        Token::Character('x'), Token::CloseParen
+    ]);
+
+    let s = String::from("#\\xd800");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
 #[test]
 fn lex_comment() {
     let s = String::from("(); (cons 4 '(+ 12 3 (* 5 6)))\n(car l)");
@@ -500,7 +932,93 @@ fn lex_vector() {
     let mut lexer = Lexer::new(s.chars());
     let tokens = lexer.lex_all();
     assert!(tokens.ok().unwrap() == vec![
-        Token::OpenVectorParen, Token::Number(1), Token::Number(2),
-        Token::Number(3), Token::CloseParen
+        Token::OpenVectorParen, Token::Number(Number::Integer(1)),
+        Token::Number(Number::Integer(2)), Token::Number(Number::Integer(3)),
+        Token::CloseParen
+    ]);
+}
+
+#[test]
+fn lex_bytevector() {
+    let s = String::from("#u8(0 255)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenBytevectorParen, Token::Number(Number::Integer(0)),
+        Token::Number(Number::Integer(255)), Token::CloseParen
+    ]);
+
+    let s = String::from("#u9(1)");
+    let mut lexer = Lexer::new(s.chars());
+    assert!(lexer.lex_all().is_err());
+}
+
+#[test]
+fn lex_inexact_numbers() {
+    let s = String::from("(1.5 -2e10 .25 -.5 1.e2 +inf.0 -inf.0)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen,
+        Token::Number(Number::Real(1.5)),
+        Token::Number(Number::Real(-2e10)),
+        Token::Number(Number::Real(0.25)),
+        Token::Number(Number::Real(-0.5)),
+        Token::Number(Number::Real(100.0)),
+        Token::Number(Number::Real(::std::f64::INFINITY)),
+        Token::Number(Number::Real(::std::f64::NEG_INFINITY)),
+        Token::CloseParen
+    ]);
+}
+
+#[test]
+fn lex_invalid_number() {
+    let s = String::from("(1.2.3)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    let error = tokens.err().unwrap();
+    assert!(error.line == 1 && error.column == 7);
+}
+
+#[test]
+fn lex_rationals() {
+    use num::{BigInt, BigRational};
+    let s = String::from("(1/2 -3/6 4/2)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    let half = BigRational::new(BigInt::from(1), BigInt::from(2));
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen,
+        Token::Number(Number::Rational(half.clone())),
+        Token::Number(Number::Rational(-half)),
+        Token::Number(Number::Integer(2)),
+        Token::CloseParen
+    ]);
+}
+
+#[test]
+fn lex_number_prefixes() {
+    let s = String::from("(#xFF #b-101 #o17 #d10 #e#x10 #X#I10 #e1.5)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    assert!(tokens.ok().unwrap() == vec![
+        Token::OpenParen,
+        Token::Number(Number::Integer(255)),
+        Token::Number(Number::Integer(-5)),
+        Token::Number(Number::Integer(15)),
+        Token::Number(Number::Integer(10)),
+        Token::Number(Number::Integer(16)),
+        Token::Number(Number::Real(16.0)),
+        Token::Number(number::parse("3/2").unwrap()),
+        Token::CloseParen
     ]);
 }
+
+#[test]
+fn lex_invalid_number_prefix() {
+    let s = String::from("(#b102)");
+    let mut lexer = Lexer::new(s.chars());
+    let tokens = lexer.lex_all();
+    let error = tokens.err().unwrap();
+    assert!(error.line == 1 && error.column == 7);
+}
@@ -1,10 +1,17 @@
 #[macro_use] extern crate mopa;
+extern crate num;
 #[macro_use] mod error;
 #[macro_use] mod macros;
 mod datum;
+mod number;
+mod symbol;
 mod environment;
+mod hash_table;
 mod lexer;
+mod parameter;
 mod parser;
+mod promise;
+mod record;
 mod repl;
 mod builtin;
 mod interpreter;
@@ -15,3 +22,6 @@ pub use datum::{Datum, Procedure};
 pub use environment::Environment;
 pub use error::RuntimeError;
 pub use interpreter::Interpreter;
+pub use number::Number;
+pub use parameter::Parameter;
+pub use symbol::Symbol;
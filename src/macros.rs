@@ -56,6 +56,12 @@ macro_rules! expect_args {
 #[macro_export]
 macro_rules! try_unwrap_arg {
     ($val:expr => i64) => (
+        match $val {
+            Datum::Number(Number::Integer(ref v)) => v.clone(),
+            _ => runtime_error!("Expected integer")
+        }
+    );
+    ($val:expr => Number) => (
         match $val {
             Datum::Number(ref v) => v.clone(),
             _ => runtime_error!("Expected number")
@@ -63,7 +69,7 @@ macro_rules! try_unwrap_arg {
     );
     ($val:expr => String) => (
         match $val {
-            Datum::String(ref v) => v,
+            Datum::String(ref v) => v.borrow().clone(),
             _ => runtime_error!("Expected string")
         }
     );
@@ -91,6 +97,18 @@ macro_rules! try_unwrap_arg {
             _ => runtime_error!("Expected vector")
         }
     );
+    ($val:expr => Bytevector) => (
+        match $val {
+            Datum::Bytevector(ref b) => b.clone(),
+            _ => runtime_error!("Expected bytevector")
+        }
+    );
+    ($val:expr => HashTable) => (
+        match $val {
+            Datum::HashTable(ref h) => h.clone(),
+            _ => runtime_error!("Expected hash table")
+        }
+    );
     ($val:expr => $t:ty) => (
         match $val {
             Datum::Ext(ref e) => {
@@ -107,6 +125,12 @@ macro_rules! try_unwrap_arg {
 #[macro_export]
 macro_rules! unwrap_arg {
     ($val:expr => i64) => (
+        match $val {
+            Datum::Number(Number::Integer(ref v)) => Ok(v.clone()),
+            _ => Err(RuntimeError{msg: "Expected integer".to_string()})
+        }
+    );
+    ($val:expr => Number) => (
         match $val {
             Datum::Number(ref v) => Ok(v.clone()),
             _ => Err(RuntimeError{msg: "Expected number".to_string()})
@@ -114,7 +138,7 @@ macro_rules! unwrap_arg {
     );
     ($val:expr => String) => (
         match $val {
-            Datum::String(ref v) => Ok(v),
+            Datum::String(ref v) => Ok(v.borrow().clone()),
             _ => Err(RuntimeError{msg: "Expected string".to_string()})
         }
     );
@@ -142,6 +166,12 @@ macro_rules! unwrap_arg {
             _ => Err(RuntimeError{msg: "Expected vector".to_string()})
         }
     );
+    ($val:expr => Bytevector) => (
+        match $val {
+            Datum::Bytevector(ref b) => Ok(b.clone()),
+            _ => Err(RuntimeError{msg: "Expected bytevector".to_string()})
+        }
+    );
     ($val:expr => $t:ty) => (
         match $val {
             Datum::Ext(ref e) => {
@@ -0,0 +1,755 @@
+use num::{self, BigInt, BigRational, Integer, One, ToPrimitive, Zero};
+use num::bigint::Sign;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::{Add, Mul, Neg, Sub};
+
+// The largest number of bits that a left shift may add to an exact integer,
+// which also bounds the size of exact powers. Anything bigger is almost
+// certainly a mistake, and would otherwise exhaust memory or take forever.
+pub const MAX_SHIFT: i64 = 1 << 24;
+
+// A number in the numeric tower. Integers and rationals are exact; reals are
+// inexact. Exact integers are stored as an i64 whenever they fit and are only
+// promoted to a BigInteger when they don't, and rationals are always kept in
+// lowest terms with a denominator other than 1, so each exact value has
+// exactly one representation.
+#[derive(Clone, Debug)]
+pub enum Number {
+    Integer(i64),
+    BigInteger(BigInt),
+    Rational(BigRational),
+    Real(f64)
+}
+
+impl Number {
+    // Creates an exact integer, demoting it to an i64 if possible.
+    pub fn from_bigint(b: BigInt) -> Number {
+        match b.to_i64() {
+            Some(n) => Number::Integer(n),
+            None => Number::BigInteger(b)
+        }
+    }
+    // Creates an exact number, demoting it to an integer if possible.
+    pub fn from_rational(r: BigRational) -> Number {
+        if r.is_integer() {
+            Number::from_bigint(r.to_integer())
+        } else {
+            Number::Rational(r)
+        }
+    }
+    pub fn is_exact(&self) -> bool {
+        match self {
+            &Number::Integer(_) | &Number::BigInteger(_) |
+                &Number::Rational(_) => true,
+            &Number::Real(_) => false
+        }
+    }
+    pub fn is_zero(&self) -> bool {
+        match self {
+            &Number::Integer(n) => n == 0,
+            &Number::Real(r) => r == 0.0,
+            // Normalized big integers and rationals are never zero.
+            &Number::BigInteger(_) | &Number::Rational(_) => false
+        }
+    }
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            &Number::Integer(n) => n as f64,
+            &Number::BigInteger(ref b) =>
+                b.to_f64().unwrap_or(::std::f64::NAN),
+            &Number::Rational(ref q) =>
+                q.to_f64().unwrap_or(::std::f64::NAN),
+            &Number::Real(r) => r
+        }
+    }
+    // Returns the value as a BigInt if the number is an exact integer.
+    pub fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            &Number::Integer(n) => Some(BigInt::from(n)),
+            &Number::BigInteger(ref b) => Some(b.clone()),
+            &Number::Rational(_) | &Number::Real(_) => None
+        }
+    }
+    // Returns the value as a BigRational if the number is exact.
+    pub fn to_rational(&self) -> Option<BigRational> {
+        match self {
+            &Number::Rational(ref q) => Some(q.clone()),
+            &Number::Real(_) => None,
+            n => n.to_bigint().map(BigRational::from_integer)
+        }
+    }
+    // Numeric comparison across exactness. Returns None if either number
+    // is a NaN.
+    pub fn compare(&self, other: &Number) -> Option<Ordering> {
+        match (self, other) {
+            (&Number::Integer(a), &Number::Integer(b)) => Some(a.cmp(&b)),
+            (&Number::Real(a), &Number::Real(b)) => a.partial_cmp(&b),
+            (&Number::Real(_), b) =>
+                b.compare(self).map(|o| o.reverse()),
+            (a, &Number::Real(r)) => {
+                // Compare exactly against the real so that large integers
+                // and rationals don't lose precision.
+                if r.is_nan() { return None; }
+                if r.is_infinite() {
+                    return Some(if r > 0.0 { Ordering::Less }
+                                else { Ordering::Greater });
+                }
+                let exact = BigRational::from_float(r).unwrap();
+                Some(a.to_rational().unwrap().cmp(&exact))
+            },
+            (a, b) =>
+                Some(a.to_rational().unwrap().cmp(&b.to_rational().unwrap()))
+        }
+    }
+    pub fn num_eq(&self, other: &Number) -> bool {
+        self.compare(other) == Some(Ordering::Equal)
+    }
+    pub fn to_exact(&self) -> Option<Number> {
+        match self {
+            &Number::Real(r) =>
+                BigRational::from_float(r).map(Number::from_rational),
+            _ => Some(self.clone())
+        }
+    }
+    pub fn to_inexact(&self) -> Number {
+        Number::Real(self.to_f64())
+    }
+    // Returns None for non-finite reals, which have no exact form.
+    pub fn numerator(&self) -> Option<Number> {
+        self.rational_part(|q| Number::from_bigint(q.numer().clone()))
+    }
+    pub fn denominator(&self) -> Option<Number> {
+        self.rational_part(|q| Number::from_bigint(q.denom().clone()))
+    }
+    // Applies the function to the exact form of the number, preserving
+    // the exactness of the original number in the result.
+    fn rational_part<F: Fn(&BigRational) -> Number>(&self, func: F) ->
+        Option<Number>
+    {
+        let exact = match self.to_exact() {
+            Some(e) => e.to_rational().unwrap(),
+            None => return None
+        };
+        let result = func(&exact);
+        if self.is_exact() { Some(result) } else { Some(result.to_inexact()) }
+    }
+    pub fn floor(&self) -> Number {
+        self.round_with(f64::floor, |q| q.floor())
+    }
+    pub fn ceiling(&self) -> Number {
+        self.round_with(f64::ceil, |q| q.ceil())
+    }
+    // Rounds to the nearest integer, with ties going to the even integer.
+    pub fn round(&self) -> Number {
+        self.round_with(f64::round_ties_even, |q| {
+            let floor = q.floor();
+            let half = BigRational::new(BigInt::one(), BigInt::from(2));
+            match (q - &floor).cmp(&half) {
+                Ordering::Less => floor,
+                Ordering::Greater => floor + BigRational::one(),
+                Ordering::Equal => {
+                    if floor.to_integer().is_even() { floor }
+                    else { floor + BigRational::one() }
+                }
+            }
+        })
+    }
+    pub fn truncate(&self) -> Number {
+        self.round_with(f64::trunc, |q| q.trunc())
+    }
+    fn round_with<F, G>(&self, real: F, rational: G) -> Number
+        where F: Fn(f64) -> f64,
+              G: Fn(&BigRational) -> BigRational
+    {
+        match self {
+            &Number::Integer(_) | &Number::BigInteger(_) => self.clone(),
+            &Number::Rational(ref q) => Number::from_rational(rational(q)),
+            &Number::Real(r) => Number::Real(real(r))
+        }
+    }
+    // Divides the numbers, returning None on exact division by zero.
+    pub fn checked_div(self, other: Number) -> Option<Number> {
+        match (self, other) {
+            (a @ Number::Real(_), b) | (a, b @ Number::Real(_)) =>
+                Some(Number::Real(a.to_f64() / b.to_f64())),
+            (a, b) => {
+                if b.is_zero() { return None; }
+                Some(Number::from_rational(
+                    a.to_rational().unwrap() / b.to_rational().unwrap()))
+            }
+        }
+    }
+    // Whether the number is an integer, regardless of exactness.
+    pub fn is_integer(&self) -> bool {
+        match self {
+            &Number::Integer(_) | &Number::BigInteger(_) => true,
+            &Number::Rational(_) => false,
+            &Number::Real(r) => r.is_finite() && r.fract() == 0.0
+        }
+    }
+    // The ordering of the number relative to zero. None for NaNs.
+    pub fn sign(&self) -> Option<Ordering> {
+        self.compare(&Number::Integer(0))
+    }
+    pub fn abs(&self) -> Number {
+        match self {
+            &Number::Real(r) => Number::Real(r.abs()),
+            n if n.sign() == Some(Ordering::Less) => -n.clone(),
+            n => n.clone()
+        }
+    }
+    // Only meaningful for integers.
+    pub fn is_even(&self) -> bool {
+        match self {
+            &Number::Integer(n) => n % 2 == 0,
+            &Number::BigInteger(ref b) => b.is_even(),
+            &Number::Rational(_) => false,
+            &Number::Real(r) => r % 2.0 == 0.0
+        }
+    }
+    // Integer division returning the quotient and remainder. With floor set,
+    // the quotient is rounded toward negative infinity; otherwise it is
+    // truncated toward zero. Both numbers must be integers and the divisor
+    // must be non-zero. The results are inexact if either number is.
+    pub fn div_rem(self, other: Number, floor: bool) -> (Number, Number) {
+        match (self, other) {
+            (Number::Integer(a), Number::Integer(b))
+                if !(a == i64::min_value() && b == -1) =>
+            {
+                if floor {
+                    (Number::Integer(Integer::div_floor(&a, &b)),
+                     Number::Integer(Integer::mod_floor(&a, &b)))
+                } else {
+                    (Number::Integer(a / b), Number::Integer(a % b))
+                }
+            },
+            (a @ Number::Real(_), b) | (a, b @ Number::Real(_)) => {
+                let (a, b) = (a.to_f64(), b.to_f64());
+                let mut r = a % b;
+                if floor && r != 0.0 && (r < 0.0) != (b < 0.0) { r += b; }
+                (Number::Real(((a - r) / b).round()), Number::Real(r))
+            },
+            (a, b) => {
+                let (a, b) = (a.to_bigint().unwrap(), b.to_bigint().unwrap());
+                let (q, r) = if floor { a.div_mod_floor(&b) }
+                             else { a.div_rem(&b) };
+                (Number::from_bigint(q), Number::from_bigint(r))
+            }
+        }
+    }
+    // Greatest common divisor of two integers.
+    pub fn gcd(self, other: Number) -> Number {
+        self.integer_op(other, |a, b| a.gcd(&b))
+    }
+    // Least common multiple of two integers.
+    pub fn lcm(self, other: Number) -> Number {
+        self.integer_op(other, |a, b| a.lcm(&b))
+    }
+    fn integer_op<F: Fn(BigInt, BigInt) -> BigInt>(self, other: Number,
+        func: F) -> Number
+    {
+        let inexact = !self.is_exact() || !other.is_exact();
+        let (a, b) = (self.to_exact().unwrap(), other.to_exact().unwrap());
+        let result = Number::from_bigint(func(a.to_bigint().unwrap(),
+            b.to_bigint().unwrap()));
+        if inexact { result.to_inexact() } else { result }
+    }
+    // Raises the number to the given power. Exact numbers raised to exact
+    // integer powers give exact results; everything else is inexact.
+    // Fails when raising exact zero to a negative power, or when an exact
+    // result would be too big to compute.
+    pub fn pow(self, exponent: Number) -> Result<Number, String> {
+        let exact_exponent = match exponent {
+            Number::Integer(e) if self.is_exact() => Some(e),
+            _ => None
+        };
+        match exact_exponent {
+            Some(e) => {
+                let base = self.to_rational().unwrap();
+                if e < 0 && base.is_zero() {
+                    return Err("Division by zero".to_string());
+                }
+                // The result has at least this many bits for every bit of
+                // the base past the first, so it's bounded like a shift.
+                let bits = ::std::cmp::max(base.numer().bits(),
+                                           base.denom().bits());
+                if (bits.saturating_sub(1) as u64)
+                    .saturating_mul(e.unsigned_abs()) > MAX_SHIFT as u64
+                {
+                    return Err(format!("Exponent {} too large", e));
+                }
+                let result = num::pow(base, e.unsigned_abs() as usize);
+                Ok(Number::from_rational(
+                    if e < 0 { result.recip() } else { result }))
+            },
+            None => Ok(Number::Real(self.to_f64().powf(exponent.to_f64())))
+        }
+    }
+    // Returns s and r such that s * s + r is the number and s is as large
+    // as possible. Only valid for exact non-negative integers.
+    pub fn exact_integer_sqrt(&self) -> (Number, Number) {
+        let n = self.to_bigint().unwrap();
+        let s = n.sqrt();
+        let r = &n - &s * &s;
+        (Number::from_bigint(s), Number::from_bigint(r))
+    }
+
+    // The bitwise operations below are only valid for exact integers,
+    // which are treated as two's complement with infinite sign extension.
+    pub fn bitwise_and(self, other: Number) -> Number {
+        bitwise(self, other, |a, b| a & b, |a, b| a & b)
+    }
+    pub fn bitwise_or(self, other: Number) -> Number {
+        bitwise(self, other, |a, b| a | b, |a, b| a | b)
+    }
+    pub fn bitwise_xor(self, other: Number) -> Number {
+        bitwise(self, other, |a, b| a ^ b, |a, b| a ^ b)
+    }
+    pub fn bitwise_not(self) -> Number {
+        -self - Number::Integer(1)
+    }
+    // Shifts left for positive amounts and right (rounding toward negative
+    // infinity) for negative amounts.
+    pub fn arithmetic_shift(self, shift: i64) -> Number {
+        match self {
+            Number::Integer(n) if shift <= 0 =>
+                Number::Integer(n >> ::std::cmp::min(shift.unsigned_abs(), 63)),
+            Number::Integer(n) if shift < 63 && (n << shift) >> shift == n =>
+                Number::Integer(n << shift),
+            n => {
+                let b = n.to_bigint().unwrap();
+                let amount = shift.unsigned_abs() as usize;
+                Number::from_bigint(if shift < 0 { b >> amount }
+                                    else { b << amount })
+            }
+        }
+    }
+    // The number of 1 bits for non-negative integers, or 0 bits for
+    // negative integers.
+    pub fn bit_count(&self) -> u64 {
+        match self {
+            &Number::Integer(n) if n < 0 => (!n).count_ones() as u64,
+            &Number::Integer(n) => n.count_ones() as u64,
+            n => {
+                let b = n.to_bigint().unwrap();
+                let magnitude = if b.sign() == Sign::Minus { !b } else { b };
+                magnitude.magnitude().count_ones()
+            }
+        }
+    }
+    // The number of bits needed to represent the integer, excluding the
+    // sign bit.
+    pub fn integer_length(&self) -> u64 {
+        match self {
+            &Number::Integer(n) if n < 0 => 64 - (!n).leading_zeros() as u64,
+            &Number::Integer(n) => 64 - n.leading_zeros() as u64,
+            n => {
+                let b = n.to_bigint().unwrap();
+                if b.sign() == Sign::Minus { (!b).bits() } else { b.bits() }
+            }
+        }
+    }
+}
+
+fn bitwise<S, B>(a: Number, b: Number, small: S, big: B) -> Number
+    where S: Fn(i64, i64) -> i64,
+          B: Fn(BigInt, BigInt) -> BigInt
+{
+    match (a, b) {
+        (Number::Integer(x), Number::Integer(y)) => Number::Integer(small(x, y)),
+        (x, y) => Number::from_bigint(big(x.to_bigint().unwrap(),
+            y.to_bigint().unwrap()))
+    }
+}
+
+// Parses the textual representation of a number, returning None if the
+// string is not a valid number.
+pub fn parse(s: &str) -> Option<Number> {
+    parse_radix(s, 10)
+}
+
+// Parses a number in the given radix. The string may begin with a radix
+// prefix (#b, #o, #d or #x), which overrides the given radix, and/or an
+// exactness prefix (#e or #i), in either order.
+pub fn parse_radix(s: &str, radix: u32) -> Option<Number> {
+    let mut radix = radix;
+    let mut radix_prefix = false;
+    let mut exactness = None;
+    let mut rest = s;
+    while rest.starts_with('#') {
+        let prefix = match rest[1..].chars().next() {
+            Some(c) => c.to_ascii_lowercase(),
+            None => return None
+        };
+        match prefix {
+            'b' | 'o' | 'd' | 'x' if !radix_prefix => {
+                radix = match prefix {
+                    'b' => 2,
+                    'o' => 8,
+                    'd' => 10,
+                    _ => 16
+                };
+                radix_prefix = true;
+            },
+            'e' if exactness.is_none() => exactness = Some(true),
+            'i' if exactness.is_none() => exactness = Some(false),
+            _ => return None
+        }
+        rest = &rest[1 + prefix.len_utf8()..];
+    }
+
+    let n = match parse_real(rest, radix, exactness == Some(true)) {
+        Some(n) => n,
+        None => return None
+    };
+    match exactness {
+        Some(true) => n.to_exact(),
+        Some(false) => Some(n.to_inexact()),
+        None => Some(n)
+    }
+}
+
+// Parses an unprefixed number. Decimals are only allowed in radix 10, and
+// are read as exact rationals if requested.
+fn parse_real(s: &str, radix: u32, exact: bool) -> Option<Number> {
+    match s {
+        "+inf.0" => return Some(Number::Real(::std::f64::INFINITY)),
+        "-inf.0" => return Some(Number::Real(::std::f64::NEG_INFINITY)),
+        "+nan.0" | "-nan.0" => return Some(Number::Real(::std::f64::NAN)),
+        _ => ()
+    }
+
+    // Handle exact rationals of the form n/d.
+    if let Some(i) = s.find('/') {
+        let (numer, denom) = (&s[..i], &s[i + 1..]);
+        if denom.starts_with('+') || denom.starts_with('-') { return None; }
+        return match (parse_integer(numer, radix), parse_integer(denom, radix)) {
+            (Some(n), Some(ref d)) if !d.is_zero() =>
+                Some(Number::from_rational(BigRational::new(n, d.clone()))),
+            _ => None
+        };
+    }
+
+    if let Some(n) = parse_integer(s, radix) {
+        return Some(Number::from_bigint(n));
+    }
+    if radix != 10 { return None; }
+
+    // Validate the syntax up front; Rust's float parser is more lenient
+    // than the Scheme grammar (e.g. it accepts "inf").
+    let (sign, unsigned) = if s.starts_with('+') || s.starts_with('-') {
+        (&s[..1], &s[1..])
+    } else {
+        ("", s)
+    };
+    let (mantissa, exponent) = match unsigned.find(|c| c == 'e' || c == 'E') {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None)
+    };
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, "")
+    };
+    let all_digits = |p: &str| p.chars().all(|c| c.is_digit(10));
+    if !all_digits(int_part) || !all_digits(frac_part) ||
+        (int_part.is_empty() && frac_part.is_empty())
+    {
+        return None;
+    }
+    let exponent = match exponent {
+        Some(exp) => {
+            let exp_digits = if exp.starts_with('+') || exp.starts_with('-') {
+                &exp[1..]
+            } else {
+                exp
+            };
+            if exp_digits.is_empty() || !all_digits(exp_digits) {
+                return None;
+            }
+            match exp.parse::<i64>() {
+                Ok(e) => e,
+                Err(_) => return None
+            }
+        },
+        None => 0
+    };
+
+    if exact {
+        // Build the exact value from the digits to avoid rounding error.
+        let digits = format!("{}{}{}", sign, int_part, frac_part);
+        let value = digits.parse::<BigInt>().unwrap();
+        let scale = exponent - frac_part.len() as i64;
+        if scale.abs() > MAX_EXACT_EXPONENT { return None; }
+        let factor = num::pow(BigInt::from(10), scale.abs() as usize);
+        Some(Number::from_rational(if scale > 0 {
+            BigRational::from_integer(value * factor)
+        } else {
+            BigRational::new(value, factor)
+        }))
+    } else {
+        s.parse::<f64>().ok().map(Number::Real)
+    }
+}
+
+// Guards against exact decimals like #e1e1000000000 that would take
+// forever to expand.
+const MAX_EXACT_EXPONENT: i64 = 10000;
+
+// Parses an optionally signed integer in the given radix.
+fn parse_integer(s: &str, radix: u32) -> Option<BigInt> {
+    let digits = if s.starts_with('+') || s.starts_with('-') {
+        &s[1..]
+    } else {
+        s
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    BigInt::parse_bytes(s.as_bytes(), radix)
+}
+
+impl Number {
+    // Formats the number in the given radix. Returns None for inexact
+    // numbers in a radix other than 10.
+    pub fn to_string_radix(&self, radix: u32) -> Option<String> {
+        match self {
+            _ if radix == 10 => Some(format!("{}", self)),
+            &Number::Integer(n) => Some(BigInt::from(n).to_str_radix(radix)),
+            &Number::BigInteger(ref b) => Some(b.to_str_radix(radix)),
+            &Number::Rational(ref q) => Some(format!("{}/{}",
+                q.numer().to_str_radix(radix), q.denom().to_str_radix(radix))),
+            &Number::Real(_) => None
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Number::Integer(n) => write!(f, "{}", n),
+            &Number::BigInteger(ref b) => write!(f, "{}", b),
+            &Number::Rational(ref q) => write!(f, "{}", q),
+            &Number::Real(r) => {
+                if r.is_nan() {
+                    write!(f, "+nan.0")
+                } else if r.is_infinite() {
+                    write!(f, "{}inf.0", if r > 0.0 {'+'} else {'-'})
+                } else {
+                    // The debug format always includes a decimal point or
+                    // exponent, which keeps the number inexact when read back.
+                    write!(f, "{:?}", r)
+                }
+            }
+        }
+    }
+}
+
+// Equality here follows eqv? semantics: numbers must have the same
+// exactness to be equal. Use num_eq() for numeric equality.
+impl PartialEq for Number {
+    fn eq(&self, other: &Number) -> bool {
+        match (self, other) {
+            (&Number::Integer(a), &Number::Integer(b)) => a == b,
+            (&Number::BigInteger(ref a), &Number::BigInteger(ref b)) => a == b,
+            (&Number::Rational(ref a), &Number::Rational(ref b)) => a == b,
+            (&Number::Real(a), &Number::Real(b)) => a.to_bits() == b.to_bits(),
+            _ => false
+        }
+    }
+}
+
+impl Eq for Number {}
+
+impl Hash for Number {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        match self {
+            &Number::Integer(n) => n.hash(state),
+            &Number::BigInteger(ref b) => b.hash(state),
+            &Number::Rational(ref q) => q.hash(state),
+            &Number::Real(r) => r.to_bits().hash(state)
+        }
+    }
+}
+
+// Applies an arithmetic operation, using the checked i64 version if both
+// numbers are small integers and falling back to arbitrary precision on
+// overflow. Exact non-integers use rational arithmetic, and inexact
+// contagion applies if either number is a real.
+fn arithmetic<C, B, Q, R>(a: Number, b: Number, checked: C, big: B,
+    rational: Q, real: R) -> Number
+    where C: Fn(i64, i64) -> Option<i64>,
+          B: Fn(BigInt, BigInt) -> BigInt,
+          Q: Fn(BigRational, BigRational) -> BigRational,
+          R: Fn(f64, f64) -> f64
+{
+    match (a, b) {
+        (Number::Integer(x), Number::Integer(y)) => {
+            match checked(x, y) {
+                Some(n) => Number::Integer(n),
+                None => Number::from_bigint(big(BigInt::from(x),
+                    BigInt::from(y)))
+            }
+        },
+        (a @ Number::Real(_), b) | (a, b @ Number::Real(_)) =>
+            Number::Real(real(a.to_f64(), b.to_f64())),
+        (a @ Number::Rational(_), b) | (a, b @ Number::Rational(_)) =>
+            Number::from_rational(rational(a.to_rational().unwrap(),
+                b.to_rational().unwrap())),
+        (a, b) => Number::from_bigint(big(a.to_bigint().unwrap(),
+            b.to_bigint().unwrap()))
+    }
+}
+
+impl Add for Number {
+    type Output = Number;
+    fn add(self, other: Number) -> Number {
+        arithmetic(self, other, |x, y| x.checked_add(y), |x, y| x + y,
+            |x, y| x + y, |x, y| x + y)
+    }
+}
+
+impl Sub for Number {
+    type Output = Number;
+    fn sub(self, other: Number) -> Number {
+        arithmetic(self, other, |x, y| x.checked_sub(y), |x, y| x - y,
+            |x, y| x - y, |x, y| x - y)
+    }
+}
+
+impl Mul for Number {
+    type Output = Number;
+    fn mul(self, other: Number) -> Number {
+        arithmetic(self, other, |x, y| x.checked_mul(y), |x, y| x * y,
+            |x, y| x * y, |x, y| x * y)
+    }
+}
+
+impl Neg for Number {
+    type Output = Number;
+    fn neg(self) -> Number {
+        match self {
+            Number::Integer(n) => match n.checked_neg() {
+                Some(m) => Number::Integer(m),
+                None => Number::from_bigint(-BigInt::from(n))
+            },
+            Number::BigInteger(b) => Number::from_bigint(-b),
+            Number::Rational(q) => Number::Rational(-q),
+            Number::Real(r) => Number::Real(-r)
+        }
+    }
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse("42"), Some(Number::Integer(42)));
+    assert_eq!(parse("+42"), Some(Number::Integer(42)));
+    assert_eq!(parse("-42"), Some(Number::Integer(-42)));
+    assert_eq!(parse("1.5"), Some(Number::Real(1.5)));
+    assert_eq!(parse(".5"), Some(Number::Real(0.5)));
+    assert_eq!(parse("-2e10"), Some(Number::Real(-2e10)));
+    assert_eq!(parse("1E-3"), Some(Number::Real(1e-3)));
+    assert_eq!(parse("+inf.0"), Some(Number::Real(::std::f64::INFINITY)));
+    assert!(parse("+nan.0").unwrap().to_f64().is_nan());
+    assert_eq!(parse("."), None);
+    assert_eq!(parse("1e"), None);
+    assert_eq!(parse("inf"), None);
+    assert_eq!(parse("1.2.3"), None);
+    assert_eq!(parse("12abc"), None);
+    assert_eq!(parse("-9223372036854775808"),
+        Some(Number::Integer(i64::min_value())));
+    assert_eq!(parse("9223372036854775808"),
+        Some(Number::BigInteger(BigInt::from(i64::max_value()) + 1)));
+}
+
+#[test]
+fn test_parse_rational() {
+    let third = BigRational::new(BigInt::from(1), BigInt::from(3));
+    assert_eq!(parse("1/3"), Some(Number::Rational(third.clone())));
+    assert_eq!(parse("2/6"), Some(Number::Rational(third.clone())));
+    assert_eq!(parse("-1/3"), Some(Number::Rational(-third)));
+    assert_eq!(parse("6/3"), Some(Number::Integer(2)));
+    assert_eq!(parse("1/0"), None);
+    assert_eq!(parse("1/-3"), None);
+    assert_eq!(parse("1.5/3"), None);
+    assert_eq!(parse("/3"), None);
+}
+
+#[test]
+fn test_parse_prefixes() {
+    assert_eq!(parse("#xFF"), Some(Number::Integer(255)));
+    assert_eq!(parse("#x-ff"), Some(Number::Integer(-255)));
+    assert_eq!(parse("#b101"), Some(Number::Integer(5)));
+    assert_eq!(parse("#o17"), Some(Number::Integer(15)));
+    assert_eq!(parse("#d19"), Some(Number::Integer(19)));
+    assert_eq!(parse("#e#x10"), Some(Number::Integer(16)));
+    assert_eq!(parse("#x#e10"), Some(Number::Integer(16)));
+    assert_eq!(parse("#i#x10"), Some(Number::Real(16.0)));
+    assert_eq!(parse("#e1.5"), parse("3/2"));
+    assert_eq!(parse("#e0.1"), parse("1/10"));
+    assert_eq!(parse("#e1e3"), Some(Number::Integer(1000)));
+    assert_eq!(parse("#i1/4"), Some(Number::Real(0.25)));
+    assert_eq!(parse("#x1/A"), parse("1/10"));
+    assert_eq!(parse_radix("ff", 16), Some(Number::Integer(255)));
+    assert_eq!(parse_radix("#d10", 16), Some(Number::Integer(10)));
+    assert_eq!(parse("#b102"), None);
+    assert_eq!(parse("#x1.5"), None);
+    assert_eq!(parse("#e#e1"), None);
+    assert_eq!(parse("#x#b1"), None);
+    assert_eq!(parse("#e+inf.0"), None);
+    assert_eq!(parse("#x"), None);
+}
+
+#[test]
+fn test_overflow_promotion() {
+    let max = Number::Integer(i64::max_value());
+    let big = max.clone() + Number::Integer(1);
+    assert_eq!(big, Number::BigInteger(BigInt::from(i64::max_value()) + 1));
+    assert_eq!(big - Number::Integer(1), max);
+    assert_eq!(-Number::Integer(i64::min_value()),
+        Number::BigInteger(-BigInt::from(i64::min_value())));
+    assert_eq!(max.compare(&Number::Real(1e300)), Some(Ordering::Less));
+}
+
+#[test]
+fn test_div_rem() {
+    let div_rem = |a: i64, b: i64, floor: bool| {
+        let (q, r) = Number::Integer(a).div_rem(Number::Integer(b), floor);
+        (format!("{}", q), format!("{}", r))
+    };
+    assert_eq!(div_rem(7, 2, true), ("3".to_string(), "1".to_string()));
+    assert_eq!(div_rem(-7, 2, true), ("-4".to_string(), "1".to_string()));
+    assert_eq!(div_rem(7, -2, true), ("-4".to_string(), "-1".to_string()));
+    assert_eq!(div_rem(-7, 2, false), ("-3".to_string(), "-1".to_string()));
+    assert_eq!(div_rem(i64::min_value(), -1, false),
+        ("9223372036854775808".to_string(), "0".to_string()));
+    let (q, r) = Number::Real(-7.0).div_rem(Number::Integer(2), true);
+    assert_eq!((q, r), (Number::Real(-4.0), Number::Real(1.0)));
+}
+
+#[test]
+fn test_bitwise() {
+    let big = Number::Integer(1).arithmetic_shift(100);
+    assert_eq!(format!("{}", big), "1267650600228229401496703205376");
+    assert_eq!(big.clone().arithmetic_shift(-100), Number::Integer(1));
+    assert_eq!(Number::Integer(-1).arithmetic_shift(-200), Number::Integer(-1));
+    assert_eq!(Number::Integer(-5).arithmetic_shift(-1), Number::Integer(-3));
+    assert_eq!(Number::Integer(1).arithmetic_shift(63),
+        Number::BigInteger(BigInt::from(1) << 63usize));
+    assert_eq!((-big.clone()).bitwise_and(Number::Integer(-1)), -big.clone());
+    assert_eq!(big.integer_length(), 101);
+    assert_eq!((-big).integer_length(), 100);
+    assert_eq!(Number::Integer(-1).integer_length(), 0);
+    assert_eq!(Number::Integer(-2).bit_count(), 1);
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(format!("{}", Number::Integer(-7)), "-7");
+    assert_eq!(format!("{}", Number::Real(1.0)), "1.0");
+    assert_eq!(format!("{}", Number::Real(-0.25)), "-0.25");
+    assert_eq!(format!("{}", Number::Real(1e21)), "1e21");
+    assert_eq!(format!("{}", Number::Real(::std::f64::NEG_INFINITY)), "-inf.0");
+    assert_eq!(format!("{}", Number::Real(::std::f64::NAN)), "+nan.0");
+}
@@ -0,0 +1,33 @@
+use datum::Datum;
+use std::cell::RefCell;
+use std::mem;
+
+// A parameter object, as made by make-parameter. The current value lives in
+// the parameter itself and parameterize swaps values in and out of it, so
+// native procedures holding on to a parameter can read it directly.
+#[derive(Debug)]
+pub struct Parameter {
+    value: RefCell<Datum>,
+    converter: Option<Datum>
+}
+
+impl Parameter {
+    pub fn new(value: Datum) -> Self {
+        Parameter {value: RefCell::new(value), converter: None}
+    }
+    // The converter procedure is applied to the values bound by parameterize.
+    // The initial value is stored as given.
+    pub fn with_converter(value: Datum, converter: Datum) -> Self {
+        Parameter {value: RefCell::new(value), converter: Some(converter)}
+    }
+    pub fn value(&self) -> Datum {
+        self.value.borrow().clone()
+    }
+    pub fn converter(&self) -> Option<&Datum> {
+        self.converter.as_ref()
+    }
+    // Sets the current value, returning the previous one.
+    pub fn replace(&self, value: Datum) -> Datum {
+        mem::replace(&mut *self.value.borrow_mut(), value)
+    }
+}
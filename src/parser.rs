@@ -1,11 +1,13 @@
 use datum::Datum;
 use lexer::Token;
-use std::cell::RefCell;
+use number::Number;
+use std::collections::{HashMap, HashSet};
 use std::iter::Peekable;
-use std::rc::Rc;
 
 pub struct Parser<I: Iterator<Item=Token>> {
-    tokens: Peekable<I>
+    tokens: Peekable<I>,
+    // Data defined by #n= labels so far.
+    labels: HashMap<u64, Datum>
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -21,45 +23,69 @@ macro_rules! parse_error {
 
 impl<I: Iterator<Item=Token>> Parser<I> {
     pub fn new(tokens: I) -> Self {
-        Parser {tokens: tokens.peekable()}
+        Parser {tokens: tokens.peekable(), labels: HashMap::new()}
     }
 
     pub fn parse_all(&mut self) -> Result<Vec<Datum>, ParseError> {
         let mut values = Vec::new();
         loop {
+            try!(self.skip_datum_comments());
             if self.tokens.peek() == None { return Ok(values); }
             values.push(try!(self.parse_datum()));
         }
     }
 
     pub fn parse_datum(&mut self) -> Result<Datum, ParseError> {
+        try!(self.skip_datum_comments());
         match self.tokens.next() {
-            Some(Token::Identifier(s)) => Ok(Datum::Symbol(s)),
-            Some(Token::String(s)) => Ok(Datum::String(s)),
+            Some(Token::Identifier(s)) => Ok(Datum::symbol(&s)),
+            Some(Token::String(s)) => Ok(Datum::string(s)),
             Some(Token::Character(c)) => Ok(Datum::Character(c)),
             Some(Token::Number(n)) => Ok(Datum::Number(n)),
             Some(Token::Boolean(b)) => Ok(Datum::Boolean(b)),
             Some(Token::OpenParen) => Ok(try!(self.parse_list())),
             Some(Token::CloseParen) => parse_error!("Unexpected closing paren"),
             Some(Token::OpenVectorParen) => Ok(try!(self.parse_vector())),
+            Some(Token::OpenBytevectorParen) =>
+                Ok(try!(self.parse_bytevector())),
             Some(Token::Dot) => parse_error!("Unexpected dot"),
+            Some(Token::DatumComment) => unreachable!(),
+            Some(Token::DatumLabel(n)) => {
+                // References to the label from within its own datum are
+                // read as a placeholder and patched once it's complete.
+                let placeholder = Datum::vector(vec![]);
+                self.labels.insert(n, placeholder.clone());
+                let datum = try!(self.parse_datum());
+                if datum.same_object(&placeholder) {
+                    parse_error!("Datum label #{}= refers to itself", n);
+                }
+                replace_placeholder(&datum, &placeholder, &datum);
+                self.labels.insert(n, datum.clone());
+                Ok(datum)
+            },
+            Some(Token::DatumReference(n)) => {
+                match self.labels.get(&n) {
+                    Some(d) => Ok(d.clone()),
+                    None => parse_error!("Undefined datum label #{}#", n)
+                }
+            },
             Some(Token::Quote) => {
-                let car = Datum::Symbol("quote".to_string());
+                let car = Datum::symbol("quote");
                 let cdar = try!(self.parse_datum());
                 Ok(Datum::pair(car, Datum::pair(cdar, Datum::EmptyList)))
             },
             Some(Token::Quasiquote) => {
-                let car = Datum::Symbol("quasiquote".to_string());
+                let car = Datum::symbol("quasiquote");
                 let cdar = try!(self.parse_datum());
                 Ok(Datum::pair(car, Datum::pair(cdar, Datum::EmptyList)))
             },
             Some(Token::Unquote) => {
-                let car = Datum::Symbol("unquote".to_string());
+                let car = Datum::symbol("unquote");
                 let cdar = try!(self.parse_datum());
                 Ok(Datum::pair(car, Datum::pair(cdar, Datum::EmptyList)))
             },
             Some(Token::UnquoteList) => {
-                let car = Datum::Symbol("unquote-splicing".to_string());
+                let car = Datum::symbol("unquote-splicing");
                 let cdar = try!(self.parse_datum());
                 Ok(Datum::pair(car, Datum::pair(cdar, Datum::EmptyList)))
             },
@@ -68,33 +94,60 @@ impl<I: Iterator<Item=Token>> Parser<I> {
     }
 
     fn parse_list(&mut self) -> Result<Datum, ParseError> {
-        if self.consume_if(|t| t == Token::CloseParen) {
-            return Ok(Datum::EmptyList);
-        }
-
-        let car = try!(self.parse_datum());
-        let cdr = if self.consume_if(|t| t == Token::Dot) {
-            let cdr = try!(self.parse_datum());
-            if !self.consume_if(|t| t == Token::CloseParen) {
-                parse_error!("Expected closing parenthesis");
+        // Read the elements iteratively so long lists don't recurse.
+        let mut elements = Vec::new();
+        loop {
+            try!(self.skip_datum_comments());
+            if self.consume_if(|t| t == Token::CloseParen) {
+                return Ok(Datum::list(elements));
             }
-            cdr
-        } else {
-            try!(self.parse_list())
-        };
-        Ok(Datum::Pair(Box::new(car), Box::new(cdr)))
+            if !elements.is_empty() && self.consume_if(|t| t == Token::Dot) {
+                let cdr = try!(self.parse_datum());
+                try!(self.skip_datum_comments());
+                if !self.consume_if(|t| t == Token::CloseParen) {
+                    parse_error!("Expected closing parenthesis");
+                }
+                elements.push(cdr);
+                return Ok(Datum::improper_list(elements));
+            }
+            elements.push(try!(self.parse_datum()));
+        }
     }
 
     fn parse_vector(&mut self) -> Result<Datum, ParseError> {
         let mut vec = Vec::new();
         loop {
+            try!(self.skip_datum_comments());
             if self.consume_if(|t| t == Token::CloseParen) {
-                return Ok(Datum::Vector(Rc::new(RefCell::new(vec))));
+                return Ok(Datum::vector(vec));
             }
             vec.push(try!(self.parse_datum()));
         }
     }
 
+    fn parse_bytevector(&mut self) -> Result<Datum, ParseError> {
+        let mut bytes = Vec::new();
+        loop {
+            try!(self.skip_datum_comments());
+            if self.consume_if(|t| t == Token::CloseParen) {
+                return Ok(Datum::bytevector(bytes));
+            }
+            match try!(self.parse_datum()) {
+                Datum::Number(Number::Integer(n)) if n >= 0 && n <= 255 =>
+                    bytes.push(n as u8),
+                _ => parse_error!("Bytevector elements must be bytes")
+            }
+        }
+    }
+
+    // Drops the datum following each #; comment.
+    fn skip_datum_comments(&mut self) -> Result<(), ParseError> {
+        while self.consume_if(|t| t == Token::DatumComment) {
+            try!(self.parse_datum());
+        }
+        Ok(())
+    }
+
     // Consumes if the token matches the given predicate; does nothing
     // otherwise.
     fn consume_if<F: Fn(Token) -> bool>(&mut self, pred: F) -> bool {
@@ -109,6 +162,41 @@ impl<I: Iterator<Item=Token>> Parser<I> {
     }
 }
 
+// Replaces references to the placeholder within the datum with the
+// value, visiting each pair and vector once. The pairs and vectors still
+// to visit are kept in a worklist rather than recursed into, so that long
+// lists don't overflow the stack.
+fn replace_placeholder(datum: &Datum, placeholder: &Datum, value: &Datum) {
+    let mut visited = HashSet::new();
+    let mut pending = vec![datum.clone()];
+    while let Some(datum) = pending.pop() {
+        match datum.object_id() {
+            Some(id) => if !visited.insert(id) { continue; },
+            None => continue
+        }
+        let mut replace = |d: &mut Datum| {
+            if d.same_object(placeholder) {
+                *d = value.clone();
+            } else if d.object_id().is_some() {
+                pending.push(d.clone());
+            }
+        };
+        match datum {
+            Datum::Pair(ref pair) => {
+                let mut pair = pair.borrow_mut();
+                replace(&mut pair.0);
+                replace(&mut pair.1);
+            },
+            Datum::Vector(ref v) => {
+                for d in v.borrow_mut().iter_mut() {
+                    replace(d);
+                }
+            },
+            _ => ()
+        }
+    }
+}
+
 macro_rules! check_parse {
     ($input:expr, $result:expr) => {{
         use lexer::Lexer;
@@ -128,12 +216,61 @@ fn parse_empty_list() {
 #[test]
 fn parse_basic_list() {
     check_parse!("(+ 2 3)", Ok(vec![
-        list!(Datum::symbol("+"), Datum::Number(2), Datum::Number(3))]));
+        list!(Datum::symbol("+"), Datum::integer(2), Datum::integer(3))]));
 }
 
 #[test]
 fn parse_nested_list() {
     check_parse!("(+ 2 (* 3 4))", Ok(vec![
-        list!(Datum::symbol("+"), Datum::Number(2),
-            list!(Datum::symbol("*"), Datum::Number(3), Datum::Number(4)))]));
+        list!(Datum::symbol("+"), Datum::integer(2),
+            list!(Datum::symbol("*"), Datum::integer(3), Datum::integer(4)))]));
+}
+
+#[test]
+fn parse_bytevector() {
+    check_parse!("#u8(1 2 3)", Ok(vec![Datum::bytevector(vec![1, 2, 3])]));
+    check_parse!("#u8()", Ok(vec![Datum::bytevector(vec![])]));
+    check_parse!("#u8(1 256)", Err(ParseError {
+        msg: "Bytevector elements must be bytes".to_string()}));
+}
+
+#[test]
+fn parse_datum_comments() {
+    check_parse!("#;(a b) c", Ok(vec![Datum::symbol("c")]));
+    check_parse!("(a #;b c)", Ok(vec![
+        list!(Datum::symbol("a"), Datum::symbol("c"))]));
+    check_parse!("(a #;b)", Ok(vec![list!(Datum::symbol("a"))]));
+    check_parse!("(a . #;b c)", Ok(vec![
+        Datum::pair(Datum::symbol("a"), Datum::symbol("c"))]));
+    check_parse!("(a . b #;c)", Ok(vec![
+        Datum::pair(Datum::symbol("a"), Datum::symbol("b"))]));
+    check_parse!("#(1 #;2 3)", Ok(vec![
+        Datum::vector(vec![Datum::integer(1), Datum::integer(3)])]));
+    check_parse!("#; #; a b c", Ok(vec![Datum::symbol("c")]));
+    check_parse!("a #;b", Ok(vec![Datum::symbol("a")]));
+    check_parse!("#;", Err(ParseError {
+        msg: "Expected datum or closing parenthesis".to_string()}));
+}
+
+#[test]
+fn parse_datum_labels() {
+    use lexer::Lexer;
+    let tokens = Lexer::new("#0=(a b . #0#)".chars()).lex_all().ok().unwrap();
+    let data = Parser::new(tokens.into_iter()).parse_all().ok().unwrap();
+    let list = data[0].clone();
+    let cddr = match list {
+        Datum::Pair(ref pair) => match pair.borrow().1 {
+            Datum::Pair(ref cdr) => cdr.borrow().1.clone(),
+            _ => panic!("Expected pair")
+        },
+        _ => panic!("Expected pair")
+    };
+    assert!(cddr.same_object(&list));
+
+    check_parse!("(#0=(x) #0#)", Ok(vec![
+        list!(list!(Datum::symbol("x")), list!(Datum::symbol("x")))]));
+    check_parse!("#0=#0#", Err(ParseError {
+        msg: "Datum label #0= refers to itself".to_string()}));
+    check_parse!("#1#", Err(ParseError {
+        msg: "Undefined datum label #1#".to_string()}));
 }
@@ -0,0 +1,77 @@
+use datum::Datum;
+use environment::Environment;
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+
+// What a promise will produce when forced.
+#[derive(Clone, Debug)]
+pub enum PromiseState {
+    // The value, once forced or when made with make-promise.
+    Done(Datum),
+    // An expression from delay, whose value is the value of the promise.
+    Delay(Datum, Rc<RefCell<Environment>>),
+    // An expression from delay-force, which evaluates to another promise to
+    // force in its place.
+    DelayForce(Datum, Rc<RefCell<Environment>>)
+}
+
+// A promise created by delay, delay-force or make-promise. Forcing a chain of
+// delay-force promises makes them all share a single state, so that the
+// result is memoized for every promise in the chain and the intermediate ones
+// can be freed as the chain is forced.
+#[derive(Debug)]
+pub struct Promise(RefCell<Rc<RefCell<PromiseState>>>);
+
+impl Promise {
+    pub fn new(state: PromiseState) -> Self {
+        Promise(RefCell::new(Rc::new(RefCell::new(state))))
+    }
+    pub fn state(&self) -> PromiseState {
+        self.0.borrow().borrow().clone()
+    }
+    pub fn value(&self) -> Option<Datum> {
+        match *self.0.borrow().borrow() {
+            PromiseState::Done(ref value) => Some(value.clone()),
+            _ => None
+        }
+    }
+    pub fn set_value(&self, value: Datum) {
+        *self.0.borrow().borrow_mut() = PromiseState::Done(value);
+    }
+    // Takes over the state of the other promise, which delay-force has
+    // produced in place of this one, and makes the other promise share it.
+    pub fn replace_with(&self, other: &Promise) {
+        let state = other.state();
+        *self.0.borrow().borrow_mut() = state;
+        let shared = self.0.borrow().clone();
+        *other.0.borrow_mut() = shared;
+    }
+    // Takes the value out of a forced promise that isn't shared with any
+    // other, so that it can be dropped without recursing.
+    pub fn take_value(&self) -> Option<Datum> {
+        let state = self.0.borrow();
+        if Rc::strong_count(&state) != 1 {
+            return None;
+        }
+        let mut state = state.borrow_mut();
+        match *state {
+            PromiseState::Done(ref mut value) =>
+                Some(mem::replace(value, Datum::Unspecified)),
+            _ => None
+        }
+    }
+}
+
+#[test]
+fn test_shared_state() {
+    let first = Promise::new(
+        PromiseState::DelayForce(Datum::integer(1), Rc::new(RefCell::new(
+            Environment::new()))));
+    let second = Promise::new(PromiseState::Done(Datum::integer(2)));
+    first.replace_with(&second);
+    assert_eq!(first.value(), Some(Datum::integer(2)));
+
+    second.set_value(Datum::integer(3));
+    assert_eq!(first.value(), Some(Datum::integer(3)));
+}
@@ -0,0 +1,51 @@
+use datum::Datum;
+use std::cell::RefCell;
+use std::rc::Rc;
+use symbol::Symbol;
+
+// A record type created by define-record-type. Each definition creates a
+// distinct type, even if it has the same name and fields as another.
+#[derive(Debug)]
+pub struct RecordType {
+    pub name: Symbol,
+    pub fields: Vec<Symbol>
+}
+
+impl RecordType {
+    // The name without the conventional angle brackets, as in <point>.
+    pub fn short_name(&self) -> &str {
+        let name = self.name.name();
+        if name.len() > 2 && name.starts_with('<') && name.ends_with('>') {
+            &name[1..name.len() - 1]
+        } else {
+            name
+        }
+    }
+    pub fn field_index(&self, field: &Symbol) -> Option<usize> {
+        self.fields.iter().position(|f| f == field)
+    }
+}
+
+// An instance of a record type. The field values are shared between all
+// copies of the record so that modifiers are visible through every alias.
+#[derive(Debug)]
+pub struct Record {
+    pub record_type: Rc<RecordType>,
+    pub fields: RefCell<Vec<Datum>>
+}
+
+impl Record {
+    pub fn is_instance(&self, record_type: &Rc<RecordType>) -> bool {
+        Rc::ptr_eq(&self.record_type, record_type)
+    }
+}
+
+// Records are equal when they have the same type and equal fields.
+impl PartialEq for Record {
+    fn eq(&self, other: &Record) -> bool {
+        self.is_instance(&other.record_type) &&
+            *self.fields.borrow() == *other.fields.borrow()
+    }
+}
+
+impl Eq for Record {}
@@ -29,15 +29,19 @@ fn get_input(prompt: &str) -> Option<String> {
 }
 
 // Continually prompt the user for input and run the specified function
-// on the resultant input.
-pub fn run<F: Fn(String) -> Result<String, String>>(prompt: &str, func: F) {
+// on the resultant input. Nothing is printed if the function returns None.
+pub fn run<F: Fn(String) -> Result<Option<String>, String>>(prompt: &str,
+    func: F)
+{
     loop {
         match get_input(prompt) {
             Some(input) => {
                 if input.len() > 0 {
-                    let result = func(input);
-                    println!("{}", result.unwrap_or_else(|e|
-                        format!("ERROR: {}", e)));
+                    match func(input) {
+                        Ok(Some(output)) => println!("{}", output),
+                        Ok(None) => (),
+                        Err(e) => println!("ERROR: {}", e)
+                    }
                 }
             },
             None => return
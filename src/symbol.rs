@@ -0,0 +1,144 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::rc::Rc;
+
+// Symbols with the same name are interned to share one allocation, so
+// symbols are compared and hashed by address rather than by name.
+// Uninterned symbols get their own allocation and so are distinct from
+// every other symbol, whatever their name.
+#[derive(Clone)]
+pub struct Symbol(Rc<str>);
+
+thread_local! {
+    static SYMBOL_TABLE: RefCell<HashMap<String, Symbol>> =
+        RefCell::new(HashMap::new());
+    static GENSYM_COUNTER: Cell<u64> = const { Cell::new(0) };
+}
+
+impl Symbol {
+    pub fn intern(name: &str) -> Symbol {
+        SYMBOL_TABLE.with(|table| {
+            if let Some(s) = table.borrow().get(name) {
+                return s.clone();
+            }
+            let s = Symbol(Rc::from(name));
+            table.borrow_mut().insert(name.to_string(), s.clone());
+            s
+        })
+    }
+    pub fn uninterned(name: &str) -> Symbol {
+        Symbol(Rc::from(name))
+    }
+    // Creates a fresh uninterned symbol named with the prefix and a counter.
+    pub fn gensym(prefix: &str) -> Symbol {
+        let n = GENSYM_COUNTER.with(|c| {
+            c.set(c.get() + 1);
+            c.get()
+        });
+        Symbol::uninterned(&format!("{}{}", prefix, n))
+    }
+    pub fn is_interned(&self) -> bool {
+        SYMBOL_TABLE.with(|table| {
+            match table.borrow().get(&*self.0) {
+                Some(s) => s == self,
+                None => false
+            }
+        })
+    }
+    pub fn name(&self) -> &str {
+        &self.0
+    }
+    fn id(&self) -> usize {
+        &*self.0 as *const str as *const u8 as usize
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.id() == other.id()
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        &*self.0 == other
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H>(&self, state: &mut H) where H: Hasher {
+        self.id().hash(state)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", &*self.0)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", &*self.0)
+    }
+}
+
+impl<'a> From<&'a str> for Symbol {
+    fn from(name: &'a str) -> Symbol {
+        Symbol::intern(name)
+    }
+}
+
+impl<'a> From<&'a String> for Symbol {
+    fn from(name: &'a String) -> Symbol {
+        Symbol::intern(name)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(name: String) -> Symbol {
+        Symbol::intern(&name)
+    }
+}
+
+impl<'a> From<&'a Symbol> for Symbol {
+    fn from(s: &'a Symbol) -> Symbol {
+        s.clone()
+    }
+}
+
+#[test]
+fn test_interning() {
+    assert_eq!(Symbol::intern("abc"), Symbol::intern("abc"));
+    assert!(Symbol::intern("abc") != Symbol::intern("abd"));
+    assert!(Symbol::intern("abc").is_interned());
+
+    let uninterned = Symbol::uninterned("abc");
+    assert!(uninterned != Symbol::intern("abc"));
+    assert!(uninterned != Symbol::uninterned("abc"));
+    assert_eq!(uninterned, uninterned.clone());
+    assert!(!uninterned.is_interned());
+    assert_eq!(uninterned.name(), "abc");
+}
+
+#[test]
+fn test_gensym() {
+    let a = Symbol::gensym("g");
+    let b = Symbol::gensym("g");
+    assert!(a != b);
+    assert!(a.name() != b.name());
+    assert!(a.starts_with("g"));
+    assert!(!a.is_interned());
+}
@@ -1,4 +1,5 @@
 use super::*;
+use std::rc::Rc;
 
 #[macro_export]
 macro_rules! systest {
@@ -104,11 +105,28 @@ fn test_car_cdr_cons() {
 #[test]
 fn test_symbol_string() {
     systest!("(symbol->string 'flying-fish)" => "\"flying-fish\"");
-    systest!("(symbol->string 'Martin)" => "\"martin\"");
+    systest!("(symbol->string 'Martin)" => "\"Martin\"");
     systest!("(symbol->string (string->symbol \"Malvina\"))" => "\"Malvina\"");
     systest!("(string->symbol \"mISSISSIppi\")" => "mISSISSIppi");
 }
 
+#[test]
+fn test_symbol_identity() {
+    systest!("(eq? 'abc (string->symbol \"abc\"))" => "#t");
+    systest!("(eq? 'abc 'abd)" => "#f");
+    systest!("(symbol-interned? 'abc)" => "#t");
+    systest!("(symbol? (gensym))" => "#t");
+    systest!("(eq? (gensym) (gensym))" => "#f");
+    systest!("(let ((g (gensym))) (eq? g g))" => "#t");
+    systest!("(symbol-interned? (gensym \"tmp\"))" => "#f");
+    systest!("(let ((g (gensym 'x))) (eq? g (string->symbol (symbol->string g))))"
+        => "#f");
+    systest!("(eq? (string->uninterned-symbol \"a\") 'a)" => "#f");
+    systest!("(symbol->string (string->uninterned-symbol \"a\"))" => "\"a\"");
+    systest!("(eq? (generate-temporary) (generate-temporary))" => "#f");
+    systest!("(gensym 1)" => Error);
+}
+
 #[test]
 fn test_predicates() {
 	systest!("(symbol? 'foo)" => "#t");
@@ -134,6 +152,11 @@ fn test_length() {
     systest!("(length '(a b c))" => "3");
     systest!("(length '(a (b) (c d e)))" => "3");
     systest!("(length '())" => "0");
+    systest!("(define x (list 1 2)) (set-cdr! (cdr x) x) (length x)" => Error);
+    systest!("(define x (list 1)) (set-cdr! x x) (length x)" => Error);
+    systest!("(length '#0=(1 2 3 . #0#))" => Error);
+    systest!("(list->vector '#0=(1 2 . #0#))" => Error);
+    systest!("(apply + '#0=(1 2 . #0#))" => Error);
 }
 
 #[test]
@@ -186,3 +209,1011 @@ fn test_map() {
     systest!("(map (lambda (x) (* x x)) '(1 2 3 4))" => "(1 4 9 16)");
     systest!("(map + '(1 2 3 4) '(2 3 4 5))" => "(3 5 7 9)");
 }
+
+#[test]
+fn test_inexact_arithmetic() {
+    systest!("1.5" => "1.5");
+    systest!("-2e10" => "-20000000000.0");
+    systest!("+inf.0" => "+inf.0");
+    systest!("-inf.0" => "-inf.0");
+    systest!("+nan.0" => "+nan.0");
+    systest!("(+ 1 0.5)" => "1.5");
+    systest!("(+ 0.5 0.25)" => "0.75");
+    systest!("(- 3 0.5)" => "2.5");
+    systest!("(- 2.5)" => "-2.5");
+    systest!("(* 2 1.5)" => "3.0");
+    systest!("(* 2 3)" => "6");
+    systest!("(= 1 1.0)" => "#t");
+    systest!("(= 1 1.5)" => "#f");
+    systest!("(= +nan.0 +nan.0)" => "#f");
+    systest!("(= 1 'a)" => Error);
+}
+
+#[test]
+fn test_exactness() {
+    systest!("(exact 2.0)" => "2");
+    systest!("(exact 5)" => "5");
+    systest!("(exact 2.5)" => "5/2");
+    systest!("(exact +inf.0)" => Error);
+    systest!("(inexact 2)" => "2.0");
+    systest!("(exact? 2)" => "#t");
+    systest!("(exact? 2.0)" => "#f");
+    systest!("(inexact? 2.0)" => "#t");
+    systest!("(floor 2.5)" => "2.0");
+    systest!("(floor -2.5)" => "-3.0");
+    systest!("(ceiling 2.5)" => "3.0");
+    systest!("(round 2.5)" => "2.0");
+    systest!("(round 3.5)" => "4.0");
+    systest!("(round -2.7)" => "-3.0");
+    systest!("(truncate -2.7)" => "-2.0");
+    systest!("(floor 7)" => "7");
+}
+
+#[test]
+fn test_bignum_arithmetic() {
+    systest!("(+ 9223372036854775807 1)" => "9223372036854775808");
+    systest!("(- -9223372036854775808 1)" => "-9223372036854775809");
+    systest!("(- -9223372036854775808)" => "9223372036854775808");
+    systest!("(* 4294967296 4294967296)" => "18446744073709551616");
+    systest!("(- (+ 9223372036854775807 1) 1)" => "9223372036854775807");
+    systest!("123456789012345678901234567890" =>
+             "123456789012345678901234567890");
+    systest!("(string->number \"-123456789012345678901234567890\")" =>
+             "-123456789012345678901234567890");
+    systest!("(letrec ((fact (lambda (n) (if (= n 0) 1 (* n (fact (- n 1))))))) \
+              (fact 25))" => "15511210043330985984000000");
+    systest!("(= 18446744073709551616 (* 4294967296 4294967296))" => "#t");
+    systest!("(= 18446744073709551616 18446744073709551616.0)" => "#t");
+    systest!("(eqv? 18446744073709551616 (* 4294967296 4294967296))" => "#t");
+    systest!("(equal? '(18446744073709551616) \
+              (list (* 4294967296 4294967296)))" => "#t");
+    systest!("(let ((h (make-hash-table))) \
+                (hash-set! h 18446744073709551616 'big) \
+                (hash-ref h (* 4294967296 4294967296)))" => "big");
+    systest!("(inexact 18446744073709551616)" => "1.8446744073709552e19");
+    systest!("(exact 1e20)" => "100000000000000000000");
+}
+
+#[test]
+fn test_rationals() {
+    systest!("1/3" => "1/3");
+    systest!("-4/6" => "-2/3");
+    systest!("4/2" => "2");
+    systest!("(/ 1 3)" => "1/3");
+    systest!("(/ 6 3)" => "2");
+    systest!("(/ 2)" => "1/2");
+    systest!("(/ 0.5)" => "2.0");
+    systest!("(/ 60 2 3)" => "10");
+    systest!("(/ 1 0)" => Error);
+    systest!("(/ 1.0 0)" => "+inf.0");
+    systest!("(/)" => Error);
+    systest!("(+ 1/3 1/6)" => "1/2");
+    systest!("(+ 1/3 2/3)" => "1");
+    systest!("(- 1/2 1)" => "-1/2");
+    systest!("(* 2/3 3/4)" => "1/2");
+    systest!("(+ 1/2 0.5)" => "1.0");
+    systest!("(= 1/2 0.5)" => "#t");
+    systest!("(= 1/3 (/ 2 6))" => "#t");
+    systest!("(equal? 1/2 (/ 1 2))" => "#t");
+    systest!("(numerator 6/4)" => "3");
+    systest!("(denominator 6/4)" => "2");
+    systest!("(denominator 5)" => "1");
+    systest!("(denominator 0.5)" => "2.0");
+    systest!("(floor -7/2)" => "-4");
+    systest!("(ceiling -7/2)" => "-3");
+    systest!("(truncate -7/2)" => "-3");
+    systest!("(round 7/2)" => "4");
+    systest!("(round 5/2)" => "2");
+    systest!("(round 8/3)" => "3");
+    systest!("(exact 0.25)" => "1/4");
+    systest!("(inexact 1/4)" => "0.25");
+    systest!("(string->number \"3/12\")" => "1/4");
+}
+
+#[test]
+fn test_number_prefixes() {
+    systest!("#xFF" => "255");
+    systest!("#b-1010" => "-10");
+    systest!("#o777" => "511");
+    systest!("#e#x10" => "16");
+    systest!("#x#i10" => "16.0");
+    systest!("#e1.25" => "5/4");
+    systest!("#i3/4" => "0.75");
+    systest!("#xZZ" => Error);
+    systest!("(number->string 255)" => "\"255\"");
+    systest!("(number->string 255 16)" => "\"ff\"");
+    systest!("(number->string -10 2)" => "\"-1010\"");
+    systest!("(number->string 1/3 2)" => "\"1/11\"");
+    systest!("(number->string 1.5)" => "\"1.5\"");
+    systest!("(number->string 1.5 2)" => Error);
+    systest!("(number->string 10 3)" => Error);
+    systest!("(string->number \"ff\" 16)" => "255");
+    systest!("(string->number \"#xff\")" => "255");
+    systest!("(string->number \"#b101\" 16)" => "5");
+    systest!("(string->number \"1e2\")" => "100.0");
+    systest!("(string->number \"12z\")" => "#f");
+    systest!("(string->number \"12\" 7)" => Error);
+}
+
+#[test]
+fn test_numeric_comparison() {
+    systest!("(<)" => Error);
+    systest!("(< 1)" => "#t");
+    systest!("(< 1 2 3)" => "#t");
+    systest!("(< 1 3 2)" => "#f");
+    systest!("(< 1 1)" => "#f");
+    systest!("(<= 1 1 2)" => "#t");
+    systest!("(> 3 2.5 1/2)" => "#t");
+    systest!("(>= 3 3 4)" => "#f");
+    systest!("(< 1 +nan.0)" => "#f");
+    systest!("(< 9223372036854775807 9223372036854775808)" => "#t");
+    systest!("(< 1 'a)" => Error);
+    systest!("(let loop ((i 0)) (if (< i 10) (loop (+ i 1)) i))" => "10");
+    systest!("(zero? 0)" => "#t");
+    systest!("(zero? 0.0)" => "#t");
+    systest!("(zero? 1/2)" => "#f");
+    systest!("(positive? 1/2)" => "#t");
+    systest!("(positive? 0)" => "#f");
+    systest!("(negative? -0.5)" => "#t");
+    systest!("(odd? 3)" => "#t");
+    systest!("(odd? 1.5)" => Error);
+    systest!("(even? 0)" => "#t");
+    systest!("(even? -4.0)" => "#t");
+    systest!("(abs -7)" => "7");
+    systest!("(abs -1/2)" => "1/2");
+    systest!("(abs -9223372036854775808)" => "9223372036854775808");
+    systest!("(max 1 3 2)" => "3");
+    systest!("(max 1 2.0)" => "2.0");
+    systest!("(min 1/2 1/3)" => "1/3");
+    systest!("(min)" => Error);
+}
+
+#[test]
+fn test_integer_division() {
+    systest!("(quotient 17 5)" => "3");
+    systest!("(quotient -17 5)" => "-3");
+    systest!("(remainder -17 5)" => "-2");
+    systest!("(modulo -17 5)" => "3");
+    systest!("(modulo 17 -5)" => "-3");
+    systest!("(modulo 17.0 5)" => "2.0");
+    systest!("(quotient 1 0)" => Error);
+    systest!("(quotient 1.5 1)" => Error);
+    systest!("(quotient 1 2 3)" => Error);
+    systest!("(call-with-values (lambda () (floor/ -7 2)) list)"
+             => "(-4 1)");
+    systest!("(call-with-values (lambda () (truncate/ -7 2)) list)"
+             => "(-3 -1)");
+    systest!("(floor-quotient -7 2)" => "-4");
+    systest!("(truncate-remainder -7 2)" => "-1");
+    systest!("(gcd)" => "0");
+    systest!("(gcd 32 -36)" => "4");
+    systest!("(gcd 32.0 36)" => "4.0");
+    systest!("(lcm)" => "1");
+    systest!("(lcm 32 -36)" => "288");
+    systest!("(expt 2 10)" => "1024");
+    systest!("(expt 2 100)" => "1267650600228229401496703205376");
+    systest!("(expt 2 -2)" => "1/4");
+    systest!("(expt 2/3 2)" => "4/9");
+    systest!("(expt 4 0.5)" => "2.0");
+    systest!("(expt 2.0 3)" => "8.0");
+    systest!("(expt 0 -1)" => Error);
+    systest!("(expt 2 (expt 10 12))" => Error);
+    systest!("(expt 1/3 (- (expt 10 12)))" => Error);
+    systest!("(expt 2 -9223372036854775808)" => Error);
+    systest!("(list (expt 1 (expt 10 12)) (expt -1 (+ (expt 10 12) 1)) \
+                    (expt 0 (expt 10 12)))" => "(1 -1 0)");
+    systest!("(integer-length (expt 2 (expt 2 24)))" => "16777217");
+    systest!("(receive (s r) (exact-integer-sqrt 17) (list s r))" => "(4 1)");
+    systest!("(receive (s r) (exact-integer-sqrt 16) (list s r))" => "(4 0)");
+    systest!("(exact-integer-sqrt -1)" => Error);
+    systest!("(exact-integer-sqrt 4.0)" => Error);
+}
+
+#[test]
+fn test_bitwise() {
+    systest!("(bitwise-and)" => "-1");
+    systest!("(bitwise-and #b1100 #b1010)" => "8");
+    systest!("(bitwise-and -1 255)" => "255");
+    systest!("(bitwise-or #b1100 #b1010 1)" => "15");
+    systest!("(bitwise-xor #b1100 #b1010)" => "6");
+    systest!("(bitwise-not 0)" => "-1");
+    systest!("(bitwise-not -10)" => "9");
+    systest!("(bitwise-and 1.0 1)" => Error);
+    systest!("(arithmetic-shift 1 10)" => "1024");
+    systest!("(arithmetic-shift 1024 -3)" => "128");
+    systest!("(arithmetic-shift -5 -1)" => "-3");
+    systest!("(arithmetic-shift 1 64)" => "18446744073709551616");
+    systest!("(arithmetic-shift (arithmetic-shift 1 64) -64)" => "1");
+    systest!("(arithmetic-shift 1 (expt 2 70))" => Error);
+    systest!("(arithmetic-shift 1 9000000000000000000)" => Error);
+    systest!("(arithmetic-shift -1 (+ (expt 2 24) 1))" => Error);
+    systest!("(list (arithmetic-shift 0 9000000000000000000) \
+                    (arithmetic-shift 0 (expt 2 70)))" => "(0 0)");
+    systest!("(integer-length (arithmetic-shift 1 (expt 2 24)))" => "16777217");
+    systest!("(list (arithmetic-shift 5 (- (expt 2 70))) \
+                    (arithmetic-shift -5 (- (expt 2 70))) \
+                    (arithmetic-shift (expt 3 50) (- (expt 2 70))) \
+                    (arithmetic-shift (- (expt 3 50)) (- (expt 2 70))))"
+             => "(0 -1 0 -1)");
+    systest!("(arithmetic-shift 1 1.5)" => Error);
+    systest!("(bitwise-and (arithmetic-shift -1 70) (arithmetic-shift 1 69))"
+             => "0");
+    systest!("(bit-count 7)" => "3");
+    systest!("(bit-count -8)" => "3");
+    systest!("(integer-length 255)" => "8");
+    systest!("(integer-length -256)" => "8");
+    systest!("(integer-length 0)" => "0");
+    systest!("(bit-set? 1 #b10)" => "#t");
+    systest!("(bit-set? 0 #b10)" => "#f");
+    systest!("(bit-set? 100 -1)" => "#t");
+    systest!("(bit-set? -1 1)" => Error);
+    systest!("(bit-field #b1101101 2 5)" => "3");
+    systest!("(bit-field -1 60 70)" => "1023");
+    systest!("(bit-field 1 3 2)" => Error);
+    systest!("(bit-field 5 0 9000000000000000000)" => "5");
+    systest!("(bit-field (expt 3 50) 0 9000000000000000000)" =>
+             "717897987691852588770249");
+    systest!("(bit-field -1 0 9000000000000000000)" => Error);
+}
+
+#[test]
+fn test_pair_mutation() {
+    systest!("(define x (list 1 2 3)) (set-car! x 'a) x" => "(a 2 3)");
+    systest!("(define x (list 1 2 3)) (set-cdr! x '(b)) x" => "(1 b)");
+    systest!("(define x (list 1 2)) (define y x) (set-car! y 9) x" => "(9 2)");
+    systest!("(define x (list 1 2)) (define y (cdr x)) (set-car! y 'b) x"
+             => "(1 b)");
+    systest!("(define (f p) (set-cdr! p 'z)) (define x (cons 1 2)) (f x) x"
+             => "(1 . z)");
+    systest!("(set-car! 5 1)" => Error);
+    systest!("(set-cdr! (list 1))" => Error);
+}
+
+#[test]
+fn test_pair_identity() {
+    systest!("(define x (list 1 2)) (eq? x x)" => "#t");
+    systest!("(define x (list 1 2)) (define y x) (eqv? x y)" => "#t");
+    systest!("(define x (list 1 2)) (eq? (cdr x) (cdr x))" => "#t");
+    systest!("(eq? (list 1 2) (list 1 2))" => "#f");
+    systest!("(equal? (list 1 2) (list 1 2))" => "#t");
+    systest!("(eq? '() '())" => "#t");
+}
+
+#[test]
+fn test_vectors() {
+    systest!("(make-vector 3 'a)" => "#(a a a)");
+    systest!("(vector-length (make-vector 4))" => "4");
+    systest!("(make-vector -1)" => Error);
+    systest!("(vector 1 \"two\" #\\3)" => "#(1 \"two\" #\\3)");
+    systest!("(vector)" => "#()");
+    systest!("(vector-ref #(1 2 3) 1)" => "2");
+    systest!("(vector-ref #(1 2 3) 3)" => Error);
+    systest!("(vector-ref #(1 2 3) -1)" => Error);
+    systest!("(define v (vector 1 2 3)) (vector-set! v 0 'x) v" => "#(x 2 3)");
+    systest!("(vector-set! (vector 1 2 3) 5 'x)" => Error);
+    systest!("(vector->list #(1 2 3))" => "(1 2 3)");
+    systest!("(vector->list #(1 2 3) 1)" => "(2 3)");
+    systest!("(vector->list #(1 2 3) 1 2)" => "(2)");
+    systest!("(vector->list #(1 2 3) 2 1)" => Error);
+    systest!("(list->vector '(1 2 3))" => "#(1 2 3)");
+    systest!("(list->vector '(1 . 2))" => Error);
+    systest!("(define v (vector 1 2 3 4)) (vector-fill! v 0 1 3) v" =>
+        "#(1 0 0 4)");
+    systest!("(define v (vector 1 2 3)) (vector-fill! v 'z) v" => "#(z z z)");
+    systest!("(vector-copy #(1 2 3) 1)" => "#(2 3)");
+    systest!("(vector-copy #(1 2 3) 1 4)" => Error);
+    systest!("(define a #(1 2 3)) (define b (vector-copy a)) (eq? a b)" =>
+        "#f");
+    systest!("(define v (vector 1 2 3 4 5)) (vector-copy! v 1 #(a b)) v" =>
+        "#(1 a b 4 5)");
+    systest!("(define v (vector 1 2 3 4 5)) (vector-copy! v 0 v 2) v" =>
+        "#(3 4 5 4 5)");
+    systest!("(define v (vector 1 2 3 4 5)) (vector-copy! v 2 v 0 3) v" =>
+        "#(1 2 1 2 3)");
+    systest!("(vector-copy! (vector 1 2) 1 #(a b))" => Error);
+    systest!("(vector-append #(1) #() #(2 3))" => "#(1 2 3)");
+    systest!("(vector-map + #(1 2 3) #(10 20))" => "#(11 22)");
+    systest!("(vector-map (lambda (x) (* x x)) #(1 2 3))" => "#(1 4 9)");
+    systest!("(define sum 0)
+              (vector-for-each (lambda (x) (set! sum (+ sum x))) #(1 2 3))
+              sum" => "6");
+}
+
+#[test]
+fn test_vector_patterns() {
+    systest!("(define-syntax swap-vec
+                (syntax-rules ()
+                  ((_ #(a b)) #(b a))))
+              (swap-vec #(1 2))" => "#(2 1)");
+    systest!("(define-syntax vec-sum
+                (syntax-rules ()
+                  ((_ #(x ...)) (+ x ...))))
+              (vec-sum #(1 2 3))" => "6");
+    systest!("(define-syntax to-vec
+                (syntax-rules ()
+                  ((_ (x ...)) (vector-length #(x ... end)))))
+              (to-vec (1 2 3))" => "4");
+    systest!("(define-syntax vec-only
+                (syntax-rules ()
+                  ((_ #(a)) a)
+                  ((_ a) 'other)))
+              (list (vec-only #(1)) (vec-only (1)))" => "(1 other)");
+}
+
+#[test]
+fn test_bytevectors() {
+    systest!("#u8(1 2 3)" => "#u8(1 2 3)");
+    systest!("(bytevector 1 2 255)" => "#u8(1 2 255)");
+    systest!("(bytevector 256)" => Error);
+    systest!("(make-bytevector 3 7)" => "#u8(7 7 7)");
+    systest!("(bytevector-length #u8(1 2 3))" => "3");
+    systest!("(bytevector-u8-ref #u8(5 6 7) 2)" => "7");
+    systest!("(bytevector-u8-ref #u8(5 6 7) 3)" => Error);
+    systest!("(define b (bytevector 1 2 3)) (bytevector-u8-set! b 1 9) b" =>
+        "#u8(1 9 3)");
+    systest!("(bytevector-u8-set! (bytevector 1) 0 -1)" => Error);
+    systest!("(bytevector-copy #u8(1 2 3 4) 1 3)" => "#u8(2 3)");
+    systest!("(define b (bytevector 1 2 3 4 5)) (bytevector-copy! b 1 b 0 2) b"
+        => "#u8(1 1 2 4 5)");
+    systest!("(bytevector-append #u8(1) #u8() #u8(2 3))" => "#u8(1 2 3)");
+    systest!("(utf8->string #u8(65 206 187))" => "\"Aλ\"");
+    systest!("(utf8->string #u8(255))" => Error);
+    systest!("(string->utf8 \"Aλ\")" => "#u8(65 206 187)");
+    systest!("(string->utf8 \"aλb\" 1 2)" => "#u8(206 187)");
+    systest!("(bytevector? #u8())" => "#t");
+    systest!("(bytevector? #(1))" => "#f");
+    systest!("(equal? #u8(1 2) (bytevector 1 2))" => "#t");
+    systest!("(eqv? #u8(1 2) (bytevector 1 2))" => "#f");
+}
+
+#[test]
+fn test_characters() {
+    systest!("#\\space" => "#\\space");
+    systest!("#\\x41" => "#\\A");
+    systest!("#\\x7" => "#\\alarm");
+    systest!("(integer->char 1)" => "#\\x1");
+    systest!("(char->integer #\\A)" => "65");
+    systest!("(char->integer #\\λ)" => "955");
+    systest!("(integer->char 955)" => "#\\λ");
+    systest!("(integer->char 55296)" => Error);
+    systest!("(integer->char -1)" => Error);
+    systest!("(char=? #\\a #\\a #\\a)" => "#t");
+    systest!("(char=? #\\a #\\A)" => "#f");
+    systest!("(char<? #\\a #\\b #\\c)" => "#t");
+    systest!("(char<? #\\a #\\c #\\b)" => "#f");
+    systest!("(char>=? #\\b #\\b #\\a)" => "#t");
+    systest!("(char-ci=? #\\a #\\A)" => "#t");
+    systest!("(char-ci<? #\\a #\\B)" => "#t");
+    systest!("(char<? #\\a 1)" => Error);
+    systest!("(char-upcase #\\a)" => "#\\A");
+    systest!("(char-upcase #\\λ)" => "#\\Λ");
+    systest!("(char-upcase #\\ß)" => "#\\ß");
+    systest!("(char-downcase #\\A)" => "#\\a");
+    systest!("(char-foldcase #\\Σ)" => "#\\σ");
+    systest!("(char-alphabetic? #\\a)" => "#t");
+    systest!("(char-alphabetic? #\\1)" => "#f");
+    systest!("(char-numeric? #\\1)" => "#t");
+    systest!("(char-numeric? #\\a)" => "#f");
+    systest!("(char-whitespace? #\\tab)" => "#t");
+    systest!("(char-whitespace? #\\a)" => "#f");
+    systest!("(char-upper-case? #\\A)" => "#t");
+    systest!("(char-lower-case? #\\A)" => "#f");
+    systest!("(digit-value #\\7)" => "7");
+    systest!("(digit-value #\\a)" => "#f");
+    systest!("(list (digit-value #\\x0664) (digit-value #\\x0AE8) \
+                    (digit-value #\\xFF19) (digit-value #\\x1D7FF))"
+             => "(4 2 9 9)");
+    systest!("(list (char-numeric? (integer->char 1633)) \
+                    (digit-value (integer->char 1633)))" => "(#t 1)");
+    systest!("(list (digit-value #\\x0BF0) (char-numeric? #\\x00BD) \
+                    (digit-value #\\x2165))" => "(#f #f #f)");
+}
+
+#[test]
+fn test_strings() {
+    systest!("(string-length \"λx\")" => "2");
+    systest!("(substring \"aλbc\" 1 3)" => "\"λb\"");
+    systest!("(substring \"aλbc\" 2)" => "\"bc\"");
+    systest!("(substring \"aλbc\" 3 5)" => Error);
+    systest!("(string-ref \"aλb\" 1)" => "#\\λ");
+    systest!("(string-ref \"abc\" 3)" => Error);
+    systest!("(string-contains \"λλab\" \"ab\")" => "2");
+    systest!("(string #\\a #\\λ)" => "\"aλ\"");
+    systest!("(make-string 3 #\\λ)" => "\"λλλ\"");
+    systest!("(string-length (make-string 2))" => "2");
+    systest!("(string-append)" => "\"\"");
+    systest!("(string-append \"a\" \"b\" \"c\")" => "\"abc\"");
+    systest!("(string-copy \"hello\" 1 3)" => "\"el\"");
+    systest!("(string->list \"abc\" 1)" => "(#\\b #\\c)");
+}
+
+#[test]
+fn test_string_mutation() {
+    systest!("(define s (make-string 3 #\\a)) (string-set! s 1 #\\λ) s" =>
+        "\"aλa\"");
+    systest!("(define s (string-copy \"abc\")) (define t s)
+              (string-set! t 0 #\\z) s" => "\"zbc\"");
+    systest!("(define s (string #\\a)) (string-set! s 1 #\\b)" => Error);
+    systest!("(define s \"abc\") (define t (string-copy s))
+              (string-set! t 0 #\\z) s" => "\"abc\"");
+    systest!("(define s (make-string 4 #\\-)) (string-fill! s #\\x 1 3) s" =>
+        "\"-xx-\"");
+    systest!("(define s (make-string 5 #\\-)) (string-copy! s 1 \"λbc\" 0 2) s"
+        => "\"-λb--\"");
+    systest!("(string-copy! (make-string 1) 0 \"ab\")" => Error);
+    systest!("(define s \"abc\") (eq? s s)" => "#t");
+    systest!("(eqv? \"abc\" \"abc\")" => "#f");
+}
+
+#[test]
+fn test_string_comparison() {
+    systest!("(string=? \"abc\" \"abc\" \"abc\")" => "#t");
+    systest!("(string=? \"abc\" \"abd\")" => "#f");
+    systest!("(string<? \"abc\" \"abd\" \"b\")" => "#t");
+    systest!("(string<? \"abc\" \"ab\")" => "#f");
+    systest!("(string>? \"b\" \"a\")" => "#t");
+    systest!("(string<=? \"a\" \"a\" \"b\")" => "#t");
+    systest!("(string>=? \"a\" \"b\")" => "#f");
+    systest!("(string-ci=? \"Straße\" \"STRASSE\")" => "#t");
+    systest!("(string-ci<? \"apple\" \"Banana\")" => "#t");
+    systest!("(string=? \"a\" 'a)" => Error);
+    systest!("(string-upcase \"straße\")" => "\"STRASSE\"");
+    systest!("(string-downcase \"ΑΒΓ\")" => "\"αβγ\"");
+    systest!("(string-foldcase \"Straße\")" => "\"strasse\"");
+}
+
+#[test]
+fn test_string_higher_order() {
+    systest!("(string-map char-upcase \"abc\")" => "\"ABC\"");
+    systest!("(string-map (lambda (a b) (if (char<? a b) a b)) \"adc\" \"bb\")"
+        => "\"ab\"");
+    systest!("(define chars '())
+              (string-for-each (lambda (c) (set! chars (cons c chars))) \"aλ\")
+              chars" => "(#\\λ #\\a)");
+}
+
+#[test]
+fn test_string_escapes() {
+    systest!("\"C:\\\\dir\"" => "\"C:\\\\dir\"");
+    systest!("(string-length \"C:\\\\dir\")" => "6");
+    systest!("(string-length \"a\\x3bb;\\n\")" => "3");
+    systest!("\"say \\\"hi\\\"\\n\"" => "\"say \\\"hi\\\"\\n\"");
+    systest!("\"two\nlines\"" => "\"two\\nlines\"");
+    systest!("\"one \\\n   two\"" => "\"one two\"");
+    systest!("(string #\\null)" => "\"\\x0;\"");
+}
+
+#[test]
+fn test_case_sensitivity() {
+    systest!("(define Foo 1) (define foo 2) (list Foo foo)" => "(1 2)");
+    systest!("(eq? 'abc 'ABC)" => "#f");
+    systest!("#!fold-case (eq? 'abc 'ABC)" => "#t");
+    systest!("#!fold-case (define Foo 1) #!no-fold-case foo" => "1");
+    systest!("(define Foo 1) #!fold-case Foo" => Error);
+
+    let mut interp = Interpreter::new();
+    interp.root_mut().define_fn("getUser", |_| Ok(Datum::string("alice")));
+    assert_eq!(format!("{}", interp.evaluate("(getUser)").unwrap()),
+        "\"alice\"");
+    assert!(interp.evaluate("(getuser)").is_err());
+
+    interp.set_fold_case(true);
+    assert_eq!(format!("{}", interp.evaluate("'Hello").unwrap()), "hello");
+    assert_eq!(format!("{}", interp.evaluate("#!no-fold-case 'Hello").unwrap()),
+        "Hello");
+}
+
+#[test]
+fn test_comments_and_pipe_symbols() {
+    systest!("(+ 1 #| 2 #| 3 |# |# 4)" => "5");
+    systest!("(+ 1 #;(* 2 3) 4)" => "5");
+    systest!("'|hello world|" => "|hello world|");
+    systest!("(symbol->string '|a\\|b|)" => "\"a|b\"");
+    systest!("(string->symbol \"\")" => "||");
+    systest!("(string->symbol \"12\")" => "|12|");
+    systest!("(string->symbol \"λ\")" => "λ");
+    systest!("(eq? 'λ (string->symbol \"λ\"))" => "#t");
+    systest!("'a»b" => Error);
+    systest!("(string->symbol \"tab\\there\")" => "|tab\\there|");
+    systest!("(eq? '|abc| 'abc)" => "#t");
+    systest!("(define |my var| 3) |my var|" => "3");
+}
+
+#[test]
+fn test_brackets() {
+    systest!("(let ([x 1] [y 2]) (+ x y))" => "3");
+    systest!("[list 1 2]" => "(1 2)");
+    systest!("(cond [(= 1 2) 'a] [else 'b])" => "b");
+    systest!("(let ([x 1)] x)" => Error);
+    systest!("'(#\\[ \"]\")" => "(#\\[ \"]\")");
+}
+
+#[test]
+fn test_datum_labels() {
+    systest!("'#0=(a b . #0#)" => "#0=(a b . #0#)");
+    systest!("(define x '#0=(1 . #0#)) (car (cdr (cdr x)))" => "1");
+    systest!("'(#0=(x) #0#)" => "((x) (x))");
+    systest!("(define x '(#0=(x) #0#)) (eq? (car x) (car (cdr x)))" => "#t");
+    systest!("'#0=#(1 #0#)" => "#0=#(1 #0#)");
+    systest!("'#0=(#0# . #0#)" => "#0=(#0# . #0#)");
+    systest!("(define v (vector 1 2)) (vector-set! v 1 v) v" => "#0=#(1 #0#)");
+    systest!("(define x (list 1 2)) (set-cdr! (cdr x) x) x" =>
+        "#0=(1 2 . #0#)");
+    systest!("(define x (list 1 2)) (set-car! (cdr x) x) x" =>
+        "#0=(1 #0#)");
+    systest!("'(a #1=(b #2=(c) #2#) #1#)" => "(a (b (c) (c)) (b (c) (c)))");
+    systest!("(let ((x (list 1))) (write-shared (list x x)))"
+             => "\"(#0=(1) #0#)\"");
+    systest!("(write-shared '#0=(a b . #0#))" => "\"#0=(a b . #0#)\"");
+    systest!("(write-shared '(1 \"two\" #(3)))" => "\"(1 \\\"two\\\" #(3))\"");
+    systest!("(define x (list 1 2)) (set-cdr! (cdr x) x) (equal? x x)" =>
+        "#t");
+    systest!("(equal? '#0=(a . #0#) '#1=(a . #1#))" => "#t");
+    let numbers: Vec<_> = (1..100001).map(|i| i.to_string()).collect();
+    systest!(&format!("(define x '#0=({} . #0#)) \
+                       (list (list-ref x 99999) (list-ref x 100000))",
+                      numbers.join(" ")) => "(100000 1)");
+    systest!("(equal? '#0=(a . #0#) '#1=(a a . #1#))" => "#t");
+    systest!("(equal? '#0=(a . #0#) '#1=(a b . #1#))" => "#f");
+    systest!("(equal? '#0=#(1 #0#) '#1=#(1 #1#))" => "#t");
+    systest!("(equal? '#0=(#0# . #0#) '#1=(#1# . #1#))" => "#t");
+}
+
+#[test]
+fn test_hash_tables() {
+    systest!("(hash-table? (make-hash-table))" => "#t");
+    systest!("(hash-table? '())" => "#f");
+    systest!("(let ((h (make-hash-table))) \
+                (hash-table-set! h 'a 1) \
+                (hash-table-set! h 'b 2) \
+                (hash-table-delete! h 'a) \
+                (list (hash-table-contains? h 'a) (hash-table-contains? h 'b) \
+                      (hash-table-count h) (hash-table-keys h) \
+                      (hash-table-values h) (hash-table->alist h)))"
+             => "(#f #t 1 (b) (2) ((b . 2)))");
+    systest!("(let ((h (make-hash-table))) (hash-table-ref h 'a))" => Error);
+    systest!("(hash-table-ref (make-hash-table) 'a (lambda () 'missing))"
+             => "missing");
+    systest!("(let ((h (make-hash-table))) \
+                (hash-table-set! h 'a 1) \
+                (list (hash-table-ref h 'a (lambda () 'missing)) \
+                      (hash-table-ref h 'a (lambda () 'missing) \
+                                      (lambda (v) (+ v 1))) \
+                      (hash-table-ref h 'b (lambda () 'missing) \
+                                      (lambda (v) (+ v 1)))))"
+             => "(1 2 missing)");
+    systest!("(hash-table-ref (make-hash-table) 'a 0)" => Error);
+    systest!("(hash-table-ref/default (make-hash-table) 'a 0)" => "0");
+
+    // Updating with and without defaults.
+    systest!("(let ((h (make-hash-table))) \
+                (hash-table-update!/default h 'n (lambda (x) (+ x 1)) 0) \
+                (hash-table-update! h 'n (lambda (x) (* x 10))) \
+                (hash-table-update! h 'm (lambda (x) (+ x 1)) (lambda () 5)) \
+                (list (hash-table-ref h 'n) (hash-table-ref h 'm)))"
+             => "(10 6)");
+    systest!("(let ((h (make-hash-table))) \
+                (hash-table-update! h 'n (lambda (x) x)))" => Error);
+    systest!("(let ((h (make-hash-table)) (sum 0)) \
+                (hash-table-set! h 1 10) \
+                (hash-table-set! h 2 20) \
+                (hash-table-walk h (lambda (k v) (set! sum (+ sum k v)))) \
+                sum)" => "33");
+
+    // Tables are shared by aliases, but not by copies.
+    systest!("(let ((h (make-hash-table))) \
+                (let ((alias h) (copy (hash-table-copy h))) \
+                  (hash-table-set! alias 'a 1) \
+                  (list (hash-table-ref/default h 'a #f) \
+                        (hash-table-ref/default copy 'a #f))))" => "(1 #f)");
+    systest!("(let ((h (make-hash-table))) (eq? h h))" => "#t");
+    systest!("(equal? (make-hash-table) (make-hash-table))" => "#f");
+
+    // Equivalences.
+    systest!("(let ((h (make-hash-table equal?))) \
+                (hash-table-set! h (list 1 \"a\") 'x) \
+                (hash-table-ref h (list 1 \"a\")))" => "x");
+    systest!("(let ((h (make-hash-table eqv?))) \
+                (hash-table-set! h (list 1) 'x) \
+                (hash-table-set! h 2 'y) \
+                (list (hash-table-contains? h (list 1)) (hash-table-ref h 2)))"
+             => "(#f y)");
+    systest!("(let ((h (make-hash-table eq?)) (k (string #\\a))) \
+                (hash-table-set! h k 'x) \
+                (list (hash-table-contains? h k) \
+                      (hash-table-contains? h (string #\\a))))" => "(#t #f)");
+    systest!("(let ((h (make-hash-table string=?))) \
+                (hash-table-set! h (string #\\a) 'x) \
+                (hash-table-ref h \"a\"))" => "x");
+    systest!("(hash-table-set! (make-hash-table string=?) 'a 1)" => Error);
+    systest!("(make-hash-table (lambda (a b) #t))" => Error);
+
+    // Procedures and extension data can be keys.
+    systest!("(let ((h (make-hash-table))) \
+                (hash-table-set! h car 'car) \
+                (hash-table-set! h cdr 'cdr) \
+                (list (hash-table-ref h car) (hash-table-ref h cdr)))"
+             => "(car cdr)");
+
+    // Cyclic data can be keys.
+    systest!("(define x (list 1 2)) (set-cdr! (cdr x) x) \
+              (define h (make-hash-table)) \
+              (hash-table-set! h x 'list) \
+              (list (hash-table-ref h x) \
+                    (hash-table-ref/default h '#0=(1 2 . #0#) #f) \
+                    (hash-table-ref/default h '#1=(1 2 1 2 . #1#) #f) \
+                    (hash-table-ref/default h '#2=(1 . #2#) #f))"
+             => "(list list list #f)");
+    systest!("(define v (vector 1 2)) (vector-set! v 1 v) \
+              (define h (make-hash-table)) \
+              (hash-table-set! h v 'vector) \
+              (list (hash-table-ref h v) \
+                    (hash-table-ref/default h '#0=#(1 #0#) #f) \
+                    (hash-table-ref/default h '#(1 #(1 2)) #f))"
+             => "(vector vector #f)");
+}
+
+#[test]
+fn test_records() {
+    let point = "(define-record-type <point> (make-point x y) point? \
+                   (x point-x set-point-x!) (y point-y)) ";
+    systest!(&format!("{}(make-point 1 2)", point) => "#<record point x: 1 y: 2>");
+    systest!(&format!("{}(point-y (make-point 1 2))", point) => "2");
+    systest!(&format!("{}(let ((p (make-point 1 2))) \
+                          (set-point-x! p 3) (point-x p))", point) => "3");
+    systest!(&format!("{}(list (point? (make-point 1 2)) (point? 5) \
+                          (record? (make-point 1 2)))", point) => "(#t #f #t)");
+    systest!(&format!("{}(point-x 5)", point) => Error);
+    systest!(&format!("{}(make-point 1)", point) => Error);
+    systest!(&format!("{}(make-point '(a) \"b\")", point)
+             => "#<record point x: (a) y: \"b\">");
+
+    // The type name is bound to the record type.
+    systest!(&format!("{}<point>", point) => "#<record-type point>");
+    systest!(&format!("{}(list (eq? <point> <point>) (record? <point>) \
+                          (point? <point>))", point) => "(#t #f #f)");
+
+    // Fields left out of the constructor start out as #f.
+    systest!("(define-record-type node (make-node value) node? \
+                (value node-value) (next node-next set-node-next!)) \
+              (make-node 1)" => "#<record node value: 1 next: #f>");
+
+    // Equality and hashing.
+    systest!(&format!("{}(list (equal? (make-point 1 '(2)) (make-point 1 '(2))) \
+                               (eqv? (make-point 1 2) (make-point 1 2)) \
+                               (equal? (make-point 1 2) (make-point 1 3)))",
+                      point) => "(#t #f #f)");
+    systest!(&format!("{}(define-record-type <other> (make-other x y) other? \
+                          (x other-x) (y other-y)) \
+                        (equal? (make-point 1 2) (make-other 1 2))", point)
+             => "#f");
+    systest!(&format!("{}(let ((h (make-hash-table))) \
+                          (hash-table-set! h (make-point 1 2) 'found) \
+                          (hash-table-ref h (make-point 1 2)))", point)
+             => "found");
+    systest!(&format!("{}(define p (make-point 1 2)) (set-point-x! p p) \
+                        (define h (make-hash-table)) \
+                        (hash-table-set! h p 'found) \
+                        (list (hash-table-ref h p) (equal? p p))", point)
+             => "(found #t)");
+
+    // Bad definitions.
+    systest!("(define-record-type <p> (make-p z) p? (x p-x))" => Error);
+    systest!("(define-record-type <p> (make-p x) p? (x p-x) (x p-x2))" => Error);
+    systest!("(define-record-type <p> (make-p x) p? (x))" => Error);
+}
+
+#[test]
+fn test_list_library() {
+    systest!("(list-tail '(a b c d) 2)" => "(c d)");
+    systest!("(list-tail '(a b) 3)" => Error);
+    systest!("(list-ref '(a b c d) 2)" => "c");
+    systest!("(list-ref '(a b) 2)" => Error);
+    systest!("(let ((l (list 1 2))) \
+                (let ((c (list-copy l))) (set-car! c 9) (list l c)))"
+             => "((1 2) (9 2))");
+    systest!("(list-copy '(1 2 . 3))" => "(1 2 . 3)");
+    systest!("(last-pair '(1 2 3))" => "(3)");
+    systest!("(last-pair '(1 2 . 3))" => "(2 . 3)");
+    systest!("(define x (list 1 2)) (set-cdr! (cdr x) x) (list-copy x)"
+             => Error);
+    systest!("(define x (list 1 2)) (set-cdr! (cdr x) x) (last-pair x)"
+             => Error);
+    systest!("(list-copy '#0=(1 . #0#))" => Error);
+    systest!("(last-pair '#0=(1 2 3 . #0#))" => Error);
+    systest!("(memq 'c '(a b c d))" => "(c d)");
+    systest!("(memq 'e '(a b c d))" => "#f");
+    systest!("(memv 101 '(100 101 102))" => "(101 102)");
+    systest!("(member (list 'a) '(b (a) c))" => "((a) c)");
+    systest!("(member 2.0 '(1 2 3) =)" => "(2 3)");
+    systest!("(assq 'b '((a 1) (b 2)))" => "(b 2)");
+    systest!("(assv 5 '((2 3) (5 7) (11 13)))" => "(5 7)");
+    systest!("(assoc (list 'a) '(((a)) ((b)) ((c))))" => "((a))");
+    systest!("(assoc 2.0 '((1 1) (2 4) (3 9)) =)" => "(2 4)");
+    systest!("(assq 'c '((a 1) (b 2)))" => "#f");
+    systest!("(assq 'a '(a b))" => Error);
+
+    systest!("(filter even? '(0 7 8 8 43 -4))" => "(0 8 8 -4)");
+    systest!("(remove even? '(0 7 8 8 43 -4))" => "(7 43)");
+    systest!("(receive (in out) (partition even? '(0 7 8 8 43 -4))
+                (list in out))" => "((0 8 8 -4) (7 43))");
+    systest!("(fold cons '() '(a b c))" => "(c b a)");
+    systest!("(fold (lambda (x y acc) (+ acc (* x y))) 0 '(1 2 3) '(4 5))"
+             => "14");
+    systest!("(fold-right cons '() '(a b c))" => "(a b c)");
+    systest!("(fold-right (lambda (x y acc) (cons (list x y) acc)) '() \
+              '(a b c) '(1 2))" => "((a 1) (b 2))");
+    systest!("(reduce + 0 '(1 2 3 4))" => "10");
+    systest!("(reduce + 0 '())" => "0");
+    systest!("(delete 3 '(1 3 2 3))" => "(1 2)");
+    systest!("(delete 3 '(1 3 5 6) <)" => "(1 3)");
+    systest!("(delete-duplicates '(a b a c a b c z))" => "(a b c z)");
+    systest!("(delete-duplicates '(1 2 3 4) (lambda (x y) (= (+ x 1) y)))"
+             => "(1 3)");
+    systest!("(iota 5)" => "(0 1 2 3 4)");
+    systest!("(iota 5 0 -1)" => "(0 -1 -2 -3 -4)");
+    systest!("(iota 3 1/2)" => "(1/2 3/2 5/2)");
+    systest!("(iota -1)" => Error);
+    systest!("(any even? '(1 3 4 5))" => "#t");
+    systest!("(any (lambda (x) (and (even? x) x)) '(1 3 5))" => "#f");
+    systest!("(any < '(3 1 4) '(2 7 1))" => "#t");
+    systest!("(every even? '())" => "#t");
+    systest!("(every (lambda (x) (and (even? x) x)) '(2 4 6))" => "6");
+    systest!("(every = '(1 2) '(1 3))" => "#f");
+    systest!("(find even? '(3 1 4 1 5 9))" => "4");
+    systest!("(find even? '(3 1 5))" => "#f");
+    systest!("(find-tail even? '(3 1 4 1 5 9))" => "(4 1 5 9)");
+    systest!("(count even? '(3 1 4 1 5 9 2 5 6))" => "3");
+    systest!("(count < '(1 2 4 8) '(2 4 6 8 10 12 14 16))" => "3");
+    systest!("(append-map (lambda (x) (list x (- x))) '(1 3 8))"
+             => "(1 -1 3 -3 8 -8)");
+    systest!("(append-map list '(1 2) '(a b))" => "(1 a 2 b)");
+    systest!("(let ((n 0)) (for-each (lambda (x) (set! n (+ n x))) '(1 2 3)) n)"
+             => "6");
+    systest!("(let ((n 0)) \
+                (for-each (lambda (x y) (set! n (+ n (* x y)))) '(1 2 3) '(4 5)) \
+                n)" => "14");
+}
+
+#[test]
+fn test_long_lists() {
+    systest!("(length (iota 100000))" => "100000");
+    systest!("(let ((l (iota 100000))) (equal? l (list-copy l)))" => "#t");
+    systest!("(let ((h (make-hash-table))) \
+                (hash-table-set! h (iota 100000) 'found) \
+                (hash-table-ref h (iota 100000)))" => "found");
+    systest!("(last-pair (filter even? (iota 100000)))" => "(99998)");
+    systest!("(fold + 0 (iota 100000))" => "4999950000");
+    systest!("(let ((n 0)) (for-each (lambda (x) (set! n (+ n 1))) (iota 100000)) n)"
+             => "100000");
+}
+
+#[test]
+fn test_unspecified_and_eof() {
+    systest!("(define x 1)" => "#<unspecified>");
+    systest!("(define (f) 1)" => "#<unspecified>");
+    systest!("(define x 1) (set! x 2)" => "#<unspecified>");
+    systest!("(if #f #f)" => "#<unspecified>");
+    systest!("(vector-set! (vector 1) 0 2)" => "#<unspecified>");
+    systest!("(null? (if #f #f))" => "#f");
+    systest!("(list (equal? (if #f #f) (if #f #f)) (eq? (if #f #f) '()))"
+             => "(#t #f)");
+
+    systest!("(eof-object)" => "#<eof>");
+    systest!("(eof-object? (eof-object))" => "#t");
+    systest!("(eof-object? '())" => "#f");
+    systest!("(eq? (eof-object) (eof-object))" => "#t");
+    systest!("(eof-object 1)" => Error);
+
+    let interp = Interpreter::new();
+    assert_eq!(interp.evaluate("(define y 2)"), Ok(Datum::Unspecified));
+}
+
+#[test]
+fn test_multiple_values() {
+    systest!("(call-with-values (lambda () (values 1 2)) cons)" => "(1 . 2)");
+    systest!("(call-with-values (lambda () (values)) list)" => "()");
+    systest!("(call-with-values (lambda () 5) list)" => "(5)");
+    systest!("(call-with-values * -)" => "-1");
+    systest!("(+ 1 (values 2))" => "3");
+    systest!("(+ 1 (values 2 3))" => Error);
+    systest!("(+ 1 (values))" => Error);
+    systest!("(begin (values 1 2) (values) 3)" => "3");
+
+    // Multiple values at the top level are all returned.
+    systest!("(values 1 2)" => Error);
+    systest!("(call-with-values (lambda () (values 1 2)) values)" => Error);
+    systest!("(values)" => "#<unspecified>");
+    let interp = Interpreter::new();
+    assert_eq!(interp.evaluate_values("(values 1 2)"),
+               Ok(vec![Datum::integer(1), Datum::integer(2)]));
+    assert_eq!(interp.evaluate_values(
+        "(call-with-values (lambda () (values 1 2)) values)"),
+        Ok(vec![Datum::integer(1), Datum::integer(2)]));
+    assert_eq!(interp.evaluate_values("(values)"), Ok(vec![]));
+    assert_eq!(interp.evaluate_values("(values 1 2) 3"),
+               Ok(vec![Datum::integer(3)]));
+    systest!("(define (f) (if #t (values 1 2) 0))
+              (call-with-values f list)" => "(1 2)");
+    systest!("(call-with-values (lambda () (apply values '(a b))) list)"
+             => "(a b)");
+
+    systest!("(let-values (((a b) (values 1 2)) ((c) (values 3))) (list a b c))"
+             => "(1 2 3)");
+    systest!("(let-values (((a . rest) (values 1 2 3)) (all (values 4 5)))
+                (list a rest all))" => "(1 (2 3) (4 5))");
+    systest!("(let ((a 'outer))
+                (let-values (((a) (values 1)) ((b) (values a))) (list a b)))"
+             => "(1 outer)");
+    systest!("(let ((a 'outer))
+                (let*-values (((a) (values 1)) ((b) (values a))) (list a b)))"
+             => "(1 1)");
+    systest!("(let-values (((a b) (values 1))) a)" => Error);
+    systest!("(let-values (((a) (values 1 2))) a)" => Error);
+    systest!("(receive (q r) (floor/ 7 2) (* q r))" => "3");
+    systest!("(receive all (values) all)" => "()");
+
+    systest!("(define-values (q r) (truncate/ 7 2)) (list q r)" => "(3 1)");
+    systest!("(define-values (x . y) (values 1 2 3)) (list x y)"
+             => "(1 (2 3))");
+    systest!("(define (f) (define-values (a b) (values 1 2)) (+ a b)) (f)"
+             => "3");
+
+    // Values are not consed up into a list.
+    systest!("(define (parse-digits s i)
+                (if (if (< i (string-length s))
+                        (char-numeric? (string-ref s i))
+                        #f)
+                    (parse-digits s (+ i 1))
+                    (values (string->number (substring s 0 i)) i)))
+              (receive (n rest) (parse-digits \"42abc\" 0) (list n rest))"
+             => "(42 2)");
+}
+
+#[test]
+fn test_promises() {
+    systest!("(force (delay (+ 1 2)))" => "3");
+    systest!("(let ((p (delay (+ 1 2)))) (list (force p) (force p)))"
+             => "(3 3)");
+    systest!("(delay 1)" => "#<promise>");
+    systest!("(force 5)" => "5");
+    systest!("(force (make-promise 7))" => "7");
+    systest!("(list (promise? (delay 1)) (promise? (make-promise 1))
+                    (promise? 1))" => "(#t #t #f)");
+    systest!("(define p (delay 1)) (eq? p (make-promise p))" => "#t");
+    systest!("(force (delay-force (delay 'a)))" => "a");
+    systest!("(force (delay-force 1))" => Error);
+
+    // The result is memoized for every alias of the promise.
+    systest!("(define count 0)
+              (define p (delay (begin (set! count (+ count 1)) count)))
+              (define q p)
+              (force p)
+              (force q)
+              count" => "1");
+
+    // Reentrant forcing keeps the first value, as in R7RS.
+    systest!("(define count 0)
+              (define p
+                (delay (begin (set! count (+ count 1))
+                              (if (> count x) count (force p)))))
+              (define x 5)
+              (force p)
+              (set! x 10)
+              (force p)" => "6");
+
+    // Chains of delay-force run in constant space.
+    systest!("(define (loop n)
+                (delay-force (if (= n 0) (make-promise 'done) (loop (- n 1)))))
+              (force (loop 30000))" => "done");
+
+    // Forced streams can be long, and are freed without recursing.
+    systest!("(define (ints n) (cons n (delay (ints (+ n 1)))))
+              (define (stream-ref s k)
+                (if (= k 0) (car s) (stream-ref (force (cdr s)) (- k 1))))
+              (define s (ints 0))
+              (define n (stream-ref s 30000))
+              (set! s #f)
+              n" => "30000");
+}
+
+#[test]
+fn test_parameters() {
+    systest!("(define p (make-parameter 10)) (p)" => "10");
+    systest!("(define p (make-parameter 10 (lambda (x) (* x 2)))) (p)" => "20");
+    systest!("(define p (make-parameter 10))
+              (list (parameterize ((p 20)) (p)) (p))" => "(20 10)");
+    systest!("(define p (make-parameter 10 (lambda (x) (* x 2))))
+              (list (parameterize ((p 3)) (p)) (p))" => "(6 20)");
+    systest!("(define p (make-parameter 1))
+              (define (f) (p))
+              (parameterize ((p 2)) (list (f) (parameterize ((p 3)) (f)) (f)))"
+             => "(2 3 2)");
+    systest!("(define p (make-parameter 1))
+              (define q (make-parameter 2))
+              (parameterize ((p (q)) (q (p))) (list (p) (q)))" => "(2 1)");
+    systest!("(define p (make-parameter 1))
+              (parameterize ((p 2)) (define x (p)) x)" => "2");
+    systest!("(procedure? (make-parameter 1))" => "#t");
+    systest!("((make-parameter 1) 2)" => Error);
+    systest!("(parameterize ((car 1)) 2)" => Error);
+    systest!("(define p (make-parameter 1 (lambda (x) (+ x 1))))
+              (parameterize ((p 'a)) (p))" => Error);
+
+    // The old value is restored when the body fails.
+    let interp = Interpreter::new();
+    interp.evaluate("(define p (make-parameter 1))").unwrap();
+    assert!(interp.evaluate("(parameterize ((p 2)) (car '()))").is_err());
+    assert_eq!(interp.evaluate("(p)"), Ok(Datum::integer(1)));
+
+    // Parameters made by the host can be read from native procedures.
+    let mut interp = Interpreter::new();
+    let user = Rc::new(Parameter::new(Datum::string("nobody")));
+    interp.root_mut().define("current-user", Datum::parameter(user.clone()));
+    interp.root_mut().define_fn("whoami", move |_| Ok(user.value()));
+    assert_eq!(interp.evaluate(
+        "(parameterize ((current-user \"alice\")) (whoami))"),
+        Ok(Datum::string("alice")));
+    assert_eq!(interp.evaluate("(whoami)"), Ok(Datum::string("nobody")));
+}
+
+#[test]
+fn test_continuations() {
+    systest!("(call/cc (lambda (k) (+ 1 (k 42))))" => "42");
+    systest!("(+ 1 (call-with-current-continuation (lambda (k) 1)))" => "2");
+    systest!("(eq? call/cc call-with-current-continuation)" => "#t");
+    systest!("(procedure? (call/cc (lambda (k) k)))" => "#t");
+    systest!("(call/cc 1)" => Error);
+    systest!("(call-with-values (lambda () (call/cc (lambda (k) (k 1 2)))) list)"
+             => "(1 2)");
+    systest!("(+ 1 (call/cc (lambda (k) (k 1 2))))" => Error);
+
+    // Escaping from a deep search.
+    systest!("(define (find-first pred lst)
+                (call/cc (lambda (return)
+                  (for-each (lambda (x) (if (pred x) (return x))) lst)
+                  #f)))
+              (list (find-first even? '(1 3 4 5)) (find-first even? '(1 3)))"
+             => "(4 #f)");
+
+    // Re-entering the same continuation several times.
+    systest!("(let ((k #f) (count 0))
+                (let ((v (call/cc (lambda (c) (set! k c) 0))))
+                  (set! count (+ count 1))
+                  (if (< v 3) (k (+ v 1)) (list v count))))" => "(3 4)");
+
+    // Generators.
+    systest!("(define (make-generator lst)
+                (define return #f)
+                (define resume #f)
+                (define (start)
+                  (for-each (lambda (x)
+                              (call/cc (lambda (k) (set! resume k) (return x))))
+                            lst)
+                  (return 'done))
+                (lambda ()
+                  (call/cc (lambda (r)
+                    (set! return r)
+                    (if resume (resume #f) (start))))))
+              (define g (make-generator '(a b c)))
+              (list (g) (g) (g) (g))" => "(a b c done)");
+
+    // Capturing and resuming continuations in tail position doesn't grow the
+    // stack.
+    systest!("(define (loop n)
+                (if (= n 0) 'done (call/cc (lambda (k) (loop (- n 1))))))
+              (loop 10000)" => "done");
+    systest!("(define (count-down n k) (if (= n 0) (k 'done) (count-down (- n 1) k)))
+              (call/cc (lambda (k) (count-down 10000 k)))" => "done");
+
+    // Parameters are unbound on escape and rebound on re-entry.
+    systest!("(define p (make-parameter 1))
+              (list (call/cc (lambda (k) (parameterize ((p 2)) (k (p))))) (p))"
+             => "(2 1)");
+    systest!("(let ((k #f) (n 0) (p (make-parameter 1)))
+                (let ((v (parameterize ((p 2))
+                           (call/cc (lambda (c) (set! k c)))
+                           (p))))
+                  (set! n (+ n 1))
+                  (if (< n 2) (k #f) (list v (p) n))))" => "(2 1 2)");
+}
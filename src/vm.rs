@@ -1,7 +1,11 @@
 use datum::{Datum, NativeProcedure, Procedure};
 use environment::Environment;
+use symbol::Symbol;
 use error::RuntimeError;
+use parameter::Parameter;
+use promise::{Promise, PromiseState};
 use std::cell::RefCell;
+use std::mem;
 use std::rc::Rc;
 
 #[derive(Debug, Clone)]
@@ -16,14 +20,56 @@ pub enum Instruction {
     // Calls the native procedure with the specified number of args from the
     // val_stack.
     CallNative(Rc<NativeProcedure>, usize),
-    // Defines the symbol corresponding with the String to the Datum at the
+    // Defines the symbol corresponding with the Symbol to the Datum at the
     // top of the val_stack. The first flag indicates whether a syntax is
     // being defined. The second flag indicates whether a set! should be done
     // instead of a define.
-    Define(Rc<RefCell<Environment>>, String, DefineType),
+    Define(Rc<RefCell<Environment>>, Symbol, DefineType),
     // Pops the top value of val_stack and checks if it is #f - skips the
     // program counter forward the specified amount if it is
     JumpIfFalse(usize),
+    // Records the height of the val_stack so that all of the values returned
+    // by the next expression can be collected, rather than just one.
+    MarkValues,
+    // Calls the procedure just below the most recent mark with the values
+    // above the mark as its already-evaluated args. Like CallProcedure, this
+    // replaces the current stack frame.
+    CallWithValues(Rc<RefCell<Environment>>),
+    // Defines each Symbol to one of the values above the most recent mark.
+    // Any extra values are bound as a list to the optional rest Symbol.
+    DefineValues(Rc<RefCell<Environment>>, Vec<Symbol>, Option<Symbol>),
+    // Forces the promise at the top of the val_stack, replacing it with the
+    // promise's value. Anything other than a promise is left as is.
+    Force,
+    // Pops the value of the expression from a promise being forced and
+    // stores it as the promise's value. If the expression was from
+    // delay-force, the promise it evaluated to is forced in its place by
+    // reusing the current stack frame.
+    ResolvePromise(Rc<Promise>),
+    // Makes a parameter from the initial value and, if the flag is set, the
+    // converter at the top of the val_stack. Leaves the parameter and the
+    // unconverted value on the val_stack for ConvertParameter.
+    MakeParameter(bool),
+    // Applies the converter of the parameter just below the top of the
+    // val_stack to the value at the top, if it has one.
+    ConvertParameter(Rc<RefCell<Environment>>),
+    // Pops the value at the top of the val_stack and makes it the value of
+    // the parameter below it.
+    InitParameter,
+    // Binds the specified number of parameters to new values, taking pairs
+    // of parameters and converted values from the val_stack. The old values
+    // are restored by Unparameterize, or when the VM stops with an error.
+    Parameterize(usize),
+    // Restores the values of the specified number of parameters bound most
+    // recently by Parameterize.
+    Unparameterize(usize),
+    // Captures the continuation of the current instruction and calls the
+    // procedure at the top of the val_stack with it. Like CallProcedure, this
+    // replaces the current stack frame.
+    CaptureContinuation(Rc<RefCell<Environment>>),
+    // Passes the specified number of values from the val_stack to the
+    // continuation, abandoning the current one.
+    Resume(Rc<Continuation>, usize),
     // Pushes the stack frame onto the call stack.
     PushStackFrame(StackFrame),
     // Pushes the Datum to the top of the val_stack.
@@ -54,18 +100,48 @@ impl StackFrame {
     }
 }
 
+// A parameter bound by parameterize, with its values outside and inside the
+// parameterize body.
+#[derive(Debug)]
+struct Parameterization {
+    parameter: Rc<Parameter>,
+    outer: Datum,
+    inner: Datum
+}
+
+// A snapshot of the VM made by call/cc. Resuming a continuation restores a
+// copy of the snapshot, so the same continuation can be resumed any number of
+// times, including after the call/cc that captured it has returned.
+#[derive(Debug)]
+pub struct Continuation {
+    call_stack: Vec<StackFrame>,
+    val_stack: Vec<Datum>,
+    value_marks: Vec<usize>,
+    parameterizations: Vec<Rc<Parameterization>>
+}
+
 pub struct VirtualMachine {
     call_stack: Vec<StackFrame>,
-    val_stack: Vec<Datum>
+    val_stack: Vec<Datum>,
+    value_marks: Vec<usize>,
+    // The parameters bound by parameterize, innermost last.
+    parameterizations: Vec<Rc<Parameterization>>
 }
 
 impl VirtualMachine {
     pub fn new() -> Self {
         //println!("creating new VM");
-        VirtualMachine {call_stack: Vec::new(), val_stack: Vec::new()}
+        VirtualMachine {
+            call_stack: Vec::new(),
+            val_stack: Vec::new(),
+            value_marks: Vec::new(),
+            parameterizations: Vec::new()
+        }
     }
+    // Returns the values of the datum; usually there's exactly one, but
+    // values can return any number.
     pub fn run(&mut self, env: Rc<RefCell<Environment>>, datum: &Datum) ->
-        Result<Datum, (RuntimeError, String)>
+        Result<Vec<Datum>, (RuntimeError, String)>
     {
         let initial_frame = StackFrame::new(vec![
             Instruction::PushValue(datum.clone()),
@@ -77,6 +153,7 @@ impl VirtualMachine {
             match self.step() {
                 Ok(more) => if !more { self.call_stack.pop(); },
                 Err(e) => {
+                    self.unwind_parameterizations();
                     // Print stack trace. TODO: Make this a return value somehow.
                     let trace_elems: Vec<_> = self.call_stack.iter()
                         .enumerate()
@@ -86,9 +163,66 @@ impl VirtualMachine {
                 }
             }
         }
-        // TODO: Return last from val_stack.
-        Ok(self.val_stack.last().
-           expect("val_stack should contain result after evaluation").clone())
+        Ok(mem::replace(&mut self.val_stack, Vec::new()))
+    }
+    // Restores the values of all parameters bound by parameterize.
+    fn unwind_parameterizations(&mut self) {
+        self.rewind_parameterizations(&[]);
+    }
+    // Changes the parameters bound by parameterize to the target ones, by
+    // restoring the values of the current bindings that aren't shared with
+    // the target and then making the rest of the target's bindings.
+    fn rewind_parameterizations(&mut self, target: &[Rc<Parameterization>]) {
+        let common = self.parameterizations.iter().zip(target.iter())
+            .take_while(|&(a, b)| Rc::ptr_eq(a, b))
+            .count();
+        while self.parameterizations.len() > common {
+            let binding = self.parameterizations.pop().unwrap();
+            binding.parameter.replace(binding.outer.clone());
+        }
+        for binding in &target[common..] {
+            binding.parameter.replace(binding.inner.clone());
+            self.parameterizations.push(binding.clone());
+        }
+    }
+    // Hands the specified number of values at the top of the val_stack to the
+    // continuation of the current instruction. Only call-with-values and the
+    // forms built on it accept any number of values. When the result is
+    // discarded the values are replaced by a single value, so that for
+    // example (values) can be used as a statement.
+    fn return_values(&mut self, n: usize) -> Result<(), RuntimeError> {
+        // The values are left for run to return at the top level.
+        let accepts_any = match self.continuation() {
+            Some(&Instruction::CallWithValues(_)) |
+            Some(&Instruction::DefineValues(..)) | None => return Ok(()),
+            Some(&Instruction::PopValue) => true,
+            _ => false
+        };
+        if !accepts_any && n != 1 {
+            runtime_error!("Expected 1 value; got {}", n);
+        }
+        let top = self.val_stack.len();
+        let mut values = self.val_stack.split_off(top - n);
+        self.val_stack.push(if values.is_empty() {
+            Datum::Unspecified
+        } else {
+            values.swap_remove(0)
+        });
+        Ok(())
+    }
+    // Finds the instruction that will receive the result of the current one,
+    // skipping over stack frames that return without running anything else.
+    // Returns None if the result goes straight back to the caller of run.
+    fn continuation(&self) -> Option<&Instruction> {
+        let fp = self.call_stack.len() - 1;
+        for (i, frame) in self.call_stack.iter().enumerate().rev() {
+            let pc = if i == fp { frame.pc + 1 } else { frame.pc };
+            match frame.instructions.get(pc) {
+                Some(&Instruction::Return) | None => continue,
+                inst => return inst
+            }
+        }
+        None
     }
     fn step(&mut self) -> Result<bool, RuntimeError> {
         // Frame pointer.
@@ -116,21 +250,26 @@ impl VirtualMachine {
                     d @ Datum::String(_) | d @ Datum::Character(_) |
                     d @ Datum::Number(_) | d @ Datum::Boolean(_) |
                     d @ Datum::Procedure(_) | d @ Datum::Vector(_) |
-                    d @ Datum::SyntaxRule(..) | d @ Datum::Ext(_) => {
+                    d @ Datum::Bytevector(_) | d @ Datum::HashTable(_) |
+                    d @ Datum::Record(_) | d @ Datum::RecordType(_) |
+                    d @ Datum::Promise(_) |
+                    d @ Datum::SyntaxRule(..) | d @ Datum::Ext(_) |
+                    d @ Datum::Unspecified | d @ Datum::Eof => {
                         self.val_stack.push(d);
                     },
-                    Datum::Pair(car, cdr) => {
+                    Datum::Pair(ref pair) => {
+                        let (car, cdr) = pair.borrow().clone();
                         let args = try!(cdr.to_vec());
                         let arg_len = args.len();
                         for arg in args {
                             self.val_stack.push(arg);
                         }
                         let instructions = vec![
-                            Instruction::PushValue(*car.clone()),
+                            Instruction::PushValue(car),
                             Instruction::Evaluate(env.clone(), false),
                             Instruction::CallProcedure(env.clone(), arg_len)
                         ];
-                        let pair = Datum::Pair(car, cdr);
+                        let pair = datum.clone();
                         if tco {
                             // Replace the current stack frame.
                             //println!("Performing tail-call optimization");
@@ -151,113 +290,11 @@ impl VirtualMachine {
                 //println!("calling procedure with {} args", n);
                 let proc_datum = self.val_stack.pop().unwrap();
                 let top = self.val_stack.len();
-                let mut args = self.val_stack.split_off(top - n);
-                let procedure = match proc_datum {
-                    Datum::Procedure(p) => p,
-                    Datum::SyntaxRule(p, name) => {
-                        // Pass the whole form as input to the syntax rule.
-                        let mut full_form = vec![Datum::Symbol(name)];
-                        full_form.append(&mut args);
-                        args = vec![Datum::list(full_form)];
-                        //println!("macro args: {:?}", args);
-                        p
-                    },
-                    _ => runtime_error!("First element in an expression must be a procedure or macro: {}", proc_datum)
-                };
-                let instructions = match procedure {
-                    Procedure::SpecialForm(ref special) => {
-                        try!(special.call(env, &args))
-                    },
-                    Procedure::Native(ref native) => {
-                        let mut instructions = Vec::new();
-                        for arg in args.iter() {
-                            instructions.push(
-                                Instruction::PushValue(arg.clone()));
-                            instructions.push(
-                                Instruction::Evaluate(env.clone(), false));
-                        }
-                        instructions.push(Instruction::CallNative(
-                            native.clone(), args.len()));
-                        instructions
-                    },
-                    Procedure::Scheme(ref s) => {
-                        let ref arg_names = s.arg_names;
-                        let ref rest_name = s.rest_name;
-                        let ref body_data = s.body_data;
-                        let ref saved_env = s.saved_env;
-                        if let &Some(_) = rest_name {
-                            if args.len() < arg_names.len() {
-                                runtime_error!("Expected at least {} argument(s) to function",
-                                    arg_names.len());
-                            }
-                        } else {
-                            if args.len() != arg_names.len() {
-                                runtime_error!("Expected {} argument(s) to function",
-                                    arg_names.len());
-                            }
-                        }
-
-                        // Set up the procedure's environment- start with the
-                        // environment saved when the function was defined and
-                        // add argument bindings. Arguments are evaluated within
-                        // the context of the outer environment.
-                        let proc_env = Rc::new(RefCell::new(
-                            Environment::with_parent(saved_env.clone())));
-                        let mut body_instructions = Vec::new();
-                        for(name, arg) in arg_names.iter().zip(args.iter()) {
-                            body_instructions.push(
-                                Instruction::PushValue(arg.clone()));
-                            body_instructions.push(
-                                Instruction::Evaluate(env.clone(), false));
-                            body_instructions.push(Instruction::Define(
-                                proc_env.clone(), name.clone(), DefineType::Define));
-                        }
-
-                        // Wrap the rest of the args up into a list.
-                        if let &Some(ref rn) = rest_name {
-                            for arg in args.iter().skip(arg_names.len()) {
-                                body_instructions.push(
-                                    Instruction::PushValue(arg.clone()));
-                            }
-                            body_instructions.push(
-                                Instruction::PushValue(
-                                    Datum::native(|args: &[Datum]| {
-                                        let elements: Vec<_> = args.iter()
-                                            .map(|e| e.clone())
-                                            .collect();
-                                        Ok(Datum::list(elements))
-                                    })));
-                            body_instructions.push(Instruction::PushStackFrame(
-                                StackFrame::new(vec![
-                                    Instruction::CallProcedure(env.clone(),
-                                        args.len() - arg_names.len())
-                                ], list!(Datum::symbol("list"),
-                                Datum::Number((args.len() - arg_names.len()) as
-                                              i64)))));
-                            body_instructions.push(Instruction::Define(
-                                proc_env.clone(), rn.clone(), DefineType::Define));
-                        }
-
-                        // Evaluate the procedure body in the new environment.
-                        for (i, expr) in body_data.iter().enumerate() {
-                            let last = i == body_data.len() - 1;
-                            body_instructions.push(
-                                Instruction::PushValue(expr.clone()));
-                            body_instructions.push(
-                                Instruction::Evaluate(proc_env.clone(), last));
-                            if !last {
-                                // Throw away the result of every expr but the
-                                // last.
-                                body_instructions.push(Instruction::PopValue);
-                            }
-                        }
-
-                        body_instructions
-                    }
-                };
+                let args = self.val_stack.split_off(top - n);
+                let instructions =
+                    try!(call_instructions(env, proc_datum, args, false));
 
                 // Replace the current stack frame with the procedure call.
-                //println!("Calling {:?} with {:?}", procedure, args);
                 self.call_stack[fp].instructions = instructions;
                 self.call_stack[fp].pc = 0;
                 return Ok(true);
@@ -266,8 +303,43 @@ impl VirtualMachine {
                 let top = self.val_stack.len();
                 //println!("Calling native with {} args. top: {}", n, top);
                 let args = self.val_stack.split_off(top - n);
-                let result = try!(native.call(&args));
-                self.val_stack.push(result);
+                let count = try!(native.call(&args, &mut self.val_stack));
+                if count != 1 {
+                    try!(self.return_values(count));
+                }
+            },
+            Instruction::MarkValues => {
+                self.value_marks.push(self.val_stack.len());
+            },
+            Instruction::CallWithValues(env) => {
+                let mark = self.value_marks.pop()
+                    .expect("Expected mark for values");
+                let args = self.val_stack.split_off(mark);
+                let proc_datum = self.val_stack.pop().unwrap();
+                let instructions =
+                    try!(call_instructions(env, proc_datum, args, true));
+                self.call_stack[fp].instructions = instructions;
+                self.call_stack[fp].pc = 0;
+                return Ok(true);
+            },
+            Instruction::DefineValues(env, names, rest_name) => {
+                let mark = self.value_marks.pop()
+                    .expect("Expected mark for values");
+                let mut values = self.val_stack.split_off(mark);
+                if values.len() < names.len() ||
+                    (rest_name.is_none() && values.len() > names.len())
+                {
+                    runtime_error!("Expected {} value(s); got {}",
+                        names.len(), values.len());
+                }
+                let rest = values.split_off(names.len());
+                let mut env = env.borrow_mut();
+                for (name, value) in names.iter().zip(values.into_iter()) {
+                    env.define(name, value);
+                }
+                if let Some(ref rn) = rest_name {
+                    env.define(rn, Datum::list(rest));
+                }
             },
             Instruction::Define(env, name, dtype) => {
                 //println!("Define value in environment");
@@ -277,9 +349,9 @@ impl VirtualMachine {
                     DefineType::DefineSyntax => {
                         let datum = self.val_stack.pop().unwrap();
                         match datum {
-                            Datum::Procedure(p) => {
+                            Datum::Procedure(ref p) => {
                                 env.borrow_mut().define(&name,
-                                    Datum::SyntaxRule(p, name.clone()));
+                                    Datum::SyntaxRule(p.clone(), name.clone()));
                             }
                             _ => runtime_error!("Expected procedure for syntax")
                         }
@@ -301,6 +373,135 @@ impl VirtualMachine {
                     _ => () //println!("Not jumping forward")
                 }
             },
+            Instruction::Force => {
+                let datum = self.val_stack.pop().unwrap();
+                match datum {
+                    Datum::Promise(ref p) => match p.state() {
+                        PromiseState::Done(value) => self.val_stack.push(value),
+                        PromiseState::Delay(expr, env) |
+                        PromiseState::DelayForce(expr, env) => {
+                            self.call_stack.push(
+                                force_frame(p.clone(), expr, env));
+                        }
+                    },
+                    _ => self.val_stack.push(datum.clone())
+                }
+            },
+            Instruction::ResolvePromise(promise) => {
+                let result = self.val_stack.pop().unwrap();
+                match promise.state() {
+                    // The promise was forced while its expression was being
+                    // evaluated; the first value wins.
+                    PromiseState::Done(value) => self.val_stack.push(value),
+                    PromiseState::Delay(..) => {
+                        promise.set_value(result.clone());
+                        self.val_stack.push(result);
+                    },
+                    PromiseState::DelayForce(..) => {
+                        match result {
+                            Datum::Promise(ref p) => promise.replace_with(p),
+                            _ => runtime_error!(
+                                "Expected promise from delay-force; got {}",
+                                result)
+                        }
+                        match promise.state() {
+                            PromiseState::Done(value) =>
+                                self.val_stack.push(value),
+                            PromiseState::Delay(expr, env) |
+                            PromiseState::DelayForce(expr, env) => {
+                                // Keep forcing in the same stack frame so
+                                // that long chains run in constant space.
+                                self.call_stack[fp] =
+                                    force_frame(promise, expr, env);
+                                return Ok(true);
+                            }
+                        }
+                    }
+                }
+            },
+            Instruction::MakeParameter(has_converter) => {
+                let converter = if has_converter {
+                    self.val_stack.pop()
+                } else {
+                    None
+                };
+                let value = self.val_stack.pop().unwrap();
+                let parameter = match converter {
+                    Some(c) => Parameter::with_converter(value.clone(), c),
+                    None => Parameter::new(value.clone())
+                };
+                self.val_stack.push(Datum::parameter(Rc::new(parameter)));
+                self.val_stack.push(value);
+            },
+            Instruction::ConvertParameter(env) => {
+                let value = self.val_stack.pop().unwrap();
+                let parameter =
+                    try!(unwrap_parameter(self.val_stack.last().unwrap()));
+                match parameter.converter() {
+                    Some(converter) => self.call_stack.push(
+                        apply_frame(env, converter.clone(), vec![value])),
+                    None => self.val_stack.push(value)
+                }
+            },
+            Instruction::InitParameter => {
+                let value = self.val_stack.pop().unwrap();
+                let parameter =
+                    try!(unwrap_parameter(self.val_stack.last().unwrap()));
+                parameter.replace(value);
+            },
+            Instruction::Parameterize(n) => {
+                let top = self.val_stack.len();
+                let bindings = self.val_stack.split_off(top - 2 * n);
+                for binding in bindings.chunks(2) {
+                    let parameter = try!(unwrap_parameter(&binding[0]));
+                    let outer = parameter.replace(binding[1].clone());
+                    self.parameterizations.push(Rc::new(Parameterization {
+                        parameter: parameter,
+                        outer: outer,
+                        inner: binding[1].clone()
+                    }));
+                }
+            },
+            Instruction::Unparameterize(n) => {
+                for _ in 0..n {
+                    let binding = self.parameterizations.pop()
+                        .expect("Expected parameterization to undo");
+                    binding.parameter.replace(binding.outer.clone());
+                }
+            },
+            Instruction::CaptureContinuation(env) => {
+                let procedure = self.val_stack.pop().unwrap();
+                // The frame is saved with its pc at this instruction, so that
+                // resuming picks up right after it.
+                let continuation = Continuation {
+                    call_stack: self.call_stack.clone(),
+                    val_stack: self.val_stack.clone(),
+                    value_marks: self.value_marks.clone(),
+                    parameterizations: self.parameterizations.clone()
+                };
+                let args = vec![Datum::continuation(Rc::new(continuation))];
+                let instructions =
+                    try!(call_instructions(env, procedure, args, true));
+                self.call_stack[fp].instructions = instructions;
+                self.call_stack[fp].pc = 0;
+                return Ok(true);
+            },
+            Instruction::Resume(continuation, n) => {
+                let top = self.val_stack.len();
+                let mut values = self.val_stack.split_off(top - n);
+                self.rewind_parameterizations(
+                    &continuation.parameterizations);
+                self.call_stack = continuation.call_stack.clone();
+                self.val_stack = continuation.val_stack.clone();
+                self.value_marks = continuation.value_marks.clone();
+                self.val_stack.append(&mut values);
+                if n != 1 {
+                    try!(self.return_values(n));
+                }
+                let fp = self.call_stack.len() - 1;
+                self.call_stack[fp].pc += 1;
+                return Ok(true);
+            },
             Instruction::PushStackFrame(frame) => self.call_stack.push(frame),
             Instruction::PushValue(d) => {
                 //println!("Pushing top value: {}", d);
@@ -321,3 +522,186 @@ impl VirtualMachine {
         Ok(true)
     }
 }
+
+// Creates a stack frame that evaluates the expression of a promise and then
+// resolves the promise with the result.
+fn force_frame(promise: Rc<Promise>, expr: Datum,
+               env: Rc<RefCell<Environment>>) -> StackFrame {
+    StackFrame::new(vec![
+        Instruction::PushValue(expr.clone()),
+        Instruction::Evaluate(env, false),
+        Instruction::ResolvePromise(promise)
+    ], expr)
+}
+
+// Creates a stack frame that calls the procedure with the already-evaluated
+// args and leaves its result on the val_stack.
+fn apply_frame(env: Rc<RefCell<Environment>>, procedure: Datum,
+               args: Vec<Datum>) -> StackFrame {
+    let expr = Datum::pair(procedure.clone(), Datum::list(args.clone()));
+    let mut instructions = vec![
+        Instruction::PushValue(procedure),
+        Instruction::MarkValues
+    ];
+    for arg in args {
+        instructions.push(Instruction::PushValue(arg));
+    }
+    instructions.push(Instruction::CallWithValues(env));
+    StackFrame::new(instructions, expr)
+}
+
+fn unwrap_parameter(datum: &Datum) -> Result<Rc<Parameter>, RuntimeError> {
+    match *datum {
+        Datum::Procedure(Procedure::Parameter(ref p)) => Ok(p.clone()),
+        _ => runtime_error!("Expected parameter; got {}", datum)
+    }
+}
+
+// Builds the instructions for calling a procedure or macro with the args. If
+// the args are already evaluated, they are passed along as is; otherwise they
+// are evaluated in the environment first.
+fn call_instructions(env: Rc<RefCell<Environment>>, proc_datum: Datum,
+                     mut args: Vec<Datum>, evaluated: bool) ->
+    Result<Vec<Instruction>, RuntimeError>
+{
+    let procedure = match proc_datum {
+        Datum::Procedure(ref p) => p.clone(),
+        Datum::SyntaxRule(ref p, ref name) if !evaluated => {
+            // Pass the whole form as input to the syntax rule.
+            let mut full_form = vec![Datum::Symbol(name.clone())];
+            full_form.append(&mut args);
+            args = vec![Datum::list(full_form)];
+            //println!("macro args: {:?}", args);
+            p.clone()
+        },
+        _ => runtime_error!("First element in an expression must be a procedure or macro: {}", proc_datum)
+    };
+    let instructions = match procedure {
+        Procedure::SpecialForm(ref special) => {
+            if evaluated {
+                // Quote the values so the special form sees them unchanged.
+                let quoted: Vec<_> = args.into_iter()
+                    .map(|arg| list!(Datum::symbol("quote"), arg))
+                    .collect();
+                try!(special.call(env, &quoted))
+            } else {
+                try!(special.call(env, &args))
+            }
+        },
+        Procedure::Native(ref native) => {
+            let mut instructions = Vec::new();
+            for arg in args.iter() {
+                instructions.push(Instruction::PushValue(arg.clone()));
+                if !evaluated {
+                    instructions.push(
+                        Instruction::Evaluate(env.clone(), false));
+                }
+            }
+            instructions.push(Instruction::CallNative(
+                native.clone(), args.len()));
+            instructions
+        },
+        Procedure::Continuation(ref continuation) => {
+            let mut instructions = Vec::new();
+            for arg in args.iter() {
+                instructions.push(Instruction::PushValue(arg.clone()));
+                if !evaluated {
+                    instructions.push(
+                        Instruction::Evaluate(env.clone(), false));
+                }
+            }
+            instructions.push(
+                Instruction::Resume(continuation.clone(), args.len()));
+            instructions
+        },
+        Procedure::Parameter(ref parameter) => {
+            if !args.is_empty() {
+                runtime_error!("Expected 0 argument(s) to parameter");
+            }
+            vec![Instruction::PushValue(parameter.value())]
+        },
+        Procedure::Scheme(ref s) => {
+            let ref arg_names = s.arg_names;
+            let ref rest_name = s.rest_name;
+            let ref body_data = s.body_data;
+            let ref saved_env = s.saved_env;
+            if let &Some(_) = rest_name {
+                if args.len() < arg_names.len() {
+                    runtime_error!("Expected at least {} argument(s) to function",
+                        arg_names.len());
+                }
+            } else {
+                if args.len() != arg_names.len() {
+                    runtime_error!("Expected {} argument(s) to function",
+                        arg_names.len());
+                }
+            }
+
+            // Set up the procedure's environment- start with the
+            // environment saved when the function was defined and
+            // add argument bindings. Arguments are evaluated within
+            // the context of the outer environment.
+            let proc_env = Rc::new(RefCell::new(
+                Environment::with_parent(saved_env.clone())));
+            let mut body_instructions = Vec::new();
+            for(name, arg) in arg_names.iter().zip(args.iter()) {
+                body_instructions.push(
+                    Instruction::PushValue(arg.clone()));
+                if !evaluated {
+                    body_instructions.push(
+                        Instruction::Evaluate(env.clone(), false));
+                }
+                body_instructions.push(Instruction::Define(
+                    proc_env.clone(), name.clone(), DefineType::Define));
+            }
+
+            // Wrap the rest of the args up into a list.
+            if let &Some(ref rn) = rest_name {
+                if evaluated {
+                    let rest = Vec::from(&args[arg_names.len()..]);
+                    body_instructions.push(
+                        Instruction::PushValue(Datum::list(rest)));
+                } else {
+                    for arg in args.iter().skip(arg_names.len()) {
+                        body_instructions.push(
+                            Instruction::PushValue(arg.clone()));
+                    }
+                    body_instructions.push(
+                        Instruction::PushValue(
+                            Datum::native(|args: &[Datum]| {
+                                let elements: Vec<_> = args.iter()
+                                    .map(|e| e.clone())
+                                    .collect();
+                                Ok(Datum::list(elements))
+                            })));
+                    body_instructions.push(Instruction::PushStackFrame(
+                        StackFrame::new(vec![
+                            Instruction::CallProcedure(env.clone(),
+                                args.len() - arg_names.len())
+                        ], list!(Datum::symbol("list"),
+                        Datum::integer((args.len() - arg_names.len()) as
+                                       i64)))));
+                }
+                body_instructions.push(Instruction::Define(
+                    proc_env.clone(), rn.clone(), DefineType::Define));
+            }
+
+            // Evaluate the procedure body in the new environment.
+            for (i, expr) in body_data.iter().enumerate() {
+                let last = i == body_data.len() - 1;
+                body_instructions.push(
+                    Instruction::PushValue(expr.clone()));
+                body_instructions.push(
+                    Instruction::Evaluate(proc_env.clone(), last));
+                if !last {
+                    // Throw away the result of every expr but the
+                    // last.
+                    body_instructions.push(Instruction::PopValue);
+                }
+            }
+
+            body_instructions
+        }
+    };
+    Ok(instructions)
+}